@@ -0,0 +1,339 @@
+//! Streaming response bodies.
+//!
+//! `Request::send_streaming` and `Client::send_streaming` return as soon as the response head has been read,
+//! leaving the body on the connection to be read through a `BodyReader` as it arrives:
+//! ```no_run
+//! # use curio::prelude::*;
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut download = Request::get("https://example.com/archive.tar").send_streaming()?;
+//!
+//!     let mut file = std::fs::File::create("archive.tar")?;
+//!     std::io::copy(&mut download.body, &mut file)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::io::{BufRead, BufReader, Read};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::codec::{self, BodyDecoder, Framing};
+use crate::headers::HeaderMap;
+use crate::pool::Pool;
+use crate::structs::{ClientConfig, Connection, Response};
+use crate::transport::Stream;
+use crate::types::{Error, Result};
+
+// how much of a framed body is read past the end of a compressed stream, such as the last chunk and trailers, to keep its connection
+const DRAIN_LIMIT: u64 = 16 * 1024;
+
+/// A request body which is read from a stream while the request is sent, see `Request::set_body_reader`.
+/// Clones share the same stream, which can only be sent once.
+#[derive(Clone)]
+pub struct BodyStream {
+    content_type: String,
+    length: Option<u64>,
+    reader: Arc<Mutex<Option<Box<dyn Read + Send>>>>,
+}
+
+impl BodyStream {
+    pub fn new<R: Read + Send + 'static, C: Into<String>>(reader: R, content_type: C, length: Option<u64>) -> BodyStream {
+        BodyStream {
+            content_type: content_type.into(),
+            length,
+            reader: Arc::new(Mutex::new(Some(Box::new(reader)))),
+        }
+    }
+
+    /// The media type of the body, sent as the `Content-Type` header
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    /// The length of the body if it is known in advance
+    pub fn length(&self) -> Option<u64> {
+        self.length
+    }
+
+    /// Takes the stream out to be sent, failing with `Error::BodyNotRewindable` if it has already been sent
+    pub(crate) fn take(&self) -> Result<Box<dyn Read + Send>> {
+        self.reader.lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+            .ok_or(Error::BodyNotRewindable)
+    }
+}
+
+impl std::fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("BodyStream")
+            .field("content_type", &self.content_type)
+            .field("length", &self.length)
+            .finish()
+    }
+}
+
+/// A response whose body has not been read yet
+#[derive(Debug)]
+pub struct StreamingResponse {
+    /// The status line and headers of the response, its `body` is `None` until `into_response` reads it
+    pub response: Response,
+    /// Reads the body as it arrives from the server
+    pub body: BodyReader,
+}
+
+impl StreamingResponse {
+    /// Reads the rest of the body into memory and returns the complete response
+    pub fn into_response(self) -> Result<Response> {
+        let StreamingResponse { mut response, mut body } = self;
+
+        let mut bytes = Vec::new();
+        body.read_to_end(&mut bytes).map_err(|e| codec::past_deadline(codec::body_error(e), body.deadline))?;
+        if !bytes.is_empty() {
+            response.body = Some(bytes);
+        }
+        response.trailers = body.trailers.clone();
+        response.compressed_size = body.compressed_size();
+
+        Ok(response)
+    }
+}
+
+impl Read for StreamingResponse {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+/// Reads a response body straight from the connection, decoding chunked and `Content-Length` framing on the fly.
+/// Compressed bodies are decoded as they are read when `ClientConfig::decompress` is set and Curio was built with the matching feature.
+/// Once the body has been read to the end the connection goes back to the pool it came from.
+/// Dropping the reader before the end closes the connection instead, as the rest of the body would still be waiting on it.
+/// A body longer than `ClientConfig::max_body_size` fails with an `ErrorKind::Other` error wrapping `Error::BodyTooLarge`.
+pub struct BodyReader {
+    framed: Option<Framed>,
+    // the decoders for a compressed body, reading from `framed`
+    decoded: Option<Box<dyn Read + Send>>,
+    pool: Pool,
+    config: ClientConfig,
+    reusable: bool,
+    deadline: Option<Instant>,
+    read: u64,
+    compressed_size: Option<u64>,
+    trailers: HeaderMap,
+}
+
+impl BodyReader {
+    pub(crate) fn new(connection: Connection, framing: Framing, pool: &Pool, config: &ClientConfig, reusable: bool, timeout: Option<Duration>, deadline: Option<Instant>) -> BodyReader {
+        let connection = TimedConnection { connection, timeout, deadline };
+        let mut reader = BodyReader {
+            framed: Some(Framed::new(BodyDecoder::new(connection, framing))),
+            decoded: None,
+            pool: pool.clone(),
+            config: config.clone(),
+            reusable,
+            deadline,
+            read: 0,
+            compressed_size: None,
+            trailers: HeaderMap::new(),
+        };
+
+        if reader.is_finished() {
+            reader.release();
+        }
+        reader
+    }
+
+    /// Decodes the body from `codings` as it is read, see `codec::encoding::prepare`
+    pub(crate) fn decode(&mut self, codings: &[String]) -> Result<()> {
+        if let Some(framed) = &self.framed {
+            self.decoded = Some(codec::encoding::decoder(Box::new(framed.clone()), codings).map_err(codec::body_error)?);
+            self.compressed_size = Some(0);
+        }
+        Ok(())
+    }
+
+    /// Whether the whole body has been read
+    pub fn is_finished(&self) -> bool {
+        match &self.framed {
+            Some(framed) => self.decoded.is_none() && framed.lock().decoder.is_finished(),
+            None => true,
+        }
+    }
+
+    /// The trailer fields sent after a chunked body, empty until the body has been read to the end
+    pub fn trailers(&self) -> &HeaderMap {
+        &self.trailers
+    }
+
+    /// How many bytes of a compressed body have been read from the connection so far, `None` if the body is not being decoded
+    pub fn compressed_size(&self) -> Option<u64> {
+        match (&self.framed, self.compressed_size) {
+            (Some(framed), Some(_)) => Some(framed.lock().read),
+            (_, size) => size,
+        }
+    }
+
+    /// Reads what is left of the framed body once the decoders have finished, so the connection can be reused.
+    /// Anything longer than `DRAIN_LIMIT` is not waited for, and the connection is closed by `release` instead
+    fn drain(&mut self) {
+        if let Some(framed) = &self.framed {
+            // a failure leaves the body unfinished, which closes the connection
+            let _ = std::io::copy(&mut framed.clone().take(DRAIN_LIMIT), &mut std::io::sink());
+        }
+    }
+
+    /// Returns the connection to the pool if the body was read to the end, and closes it otherwise
+    fn release(&mut self) {
+        // the decoders hold the other handle to the framed body
+        self.decoded = None;
+
+        if let Some(framed) = self.framed.take() {
+            let framed = match Arc::try_unwrap(framed.0) {
+                Ok(framed) => framed.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner()),
+                Err(_) => unreachable!("the decoders were dropped above"),
+            };
+
+            if self.compressed_size.is_some() {
+                self.compressed_size = Some(framed.read);
+            }
+            if let Some(trailers) = framed.decoder.trailers() {
+                self.trailers = trailers.clone();
+            }
+
+            let finished = framed.decoder.is_finished();
+            let connection = framed.decoder.into_inner().connection;
+            if finished {
+                self.pool.checkin(connection, self.reusable, &self.config);
+            } else {
+                self.pool.discard(connection);
+            }
+        }
+    }
+}
+
+impl Read for BodyReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let framed = match &self.framed {
+            Some(framed) => framed.clone(),
+            None => return Ok(0),
+        };
+
+        // a decoded body ends when the decoders do, a framed body as soon as its last byte has been read
+        let (result, finished) = match self.decoded.as_mut() {
+            Some(decoded) => {
+                let result = decoded.read(buf);
+                let finished = matches!(result, Ok(0)) && !buf.is_empty();
+                (result, finished)
+            }
+            None => {
+                let mut framed = framed.lock();
+                let result = framed.read(buf);
+                (result, framed.decoder.is_finished())
+            }
+        };
+        drop(framed);
+
+        let read = match result {
+            Ok(read) => read,
+            Err(e) => {
+                self.release();
+                return Err(e);
+            }
+        };
+
+        self.read += read as u64;
+        if let Some(limit) = self.config.max_body_size.filter(|limit| self.read > *limit) {
+            self.release();
+            return Err(std::io::Error::other(Error::BodyTooLarge { limit }));
+        }
+
+        if finished {
+            if self.decoded.is_some() {
+                self.drain();
+            }
+            self.release();
+        }
+        Ok(read)
+    }
+}
+
+impl Drop for BodyReader {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+impl std::fmt::Debug for BodyReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("BodyReader")
+            .field("finished", &self.is_finished())
+            .field("read", &self.read)
+            .field("compressed_size", &self.compressed_size())
+            .finish()
+    }
+}
+
+struct FramedBody {
+    decoder: BodyDecoder<TimedConnection>,
+    // the bytes of the body read from the connection, before any decoding
+    read: u64,
+}
+
+/// The framed body, shared between the `BodyReader` and the decoders of a compressed body so the connection can be taken back afterwards
+#[derive(Clone)]
+struct Framed(Arc<Mutex<FramedBody>>);
+
+impl Framed {
+    fn new(decoder: BodyDecoder<TimedConnection>) -> Framed {
+        Framed(Arc::new(Mutex::new(FramedBody { decoder, read: 0 })))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FramedBody> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Read for FramedBody {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.decoder.read(buf)?;
+        self.read += read as u64;
+        Ok(read)
+    }
+}
+
+impl Read for Framed {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.lock().read(buf)
+    }
+}
+
+/// A connection which sets its read timeout before every read, see `codec::TimedReader`
+struct TimedConnection {
+    connection: Connection,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+}
+
+impl TimedConnection {
+    fn stream(&mut self) -> std::io::Result<&mut BufReader<Box<dyn Stream>>> {
+        codec::arm_read_timeout(&self.connection.stream, self.timeout, self.deadline)?;
+        Ok(&mut self.connection.stream)
+    }
+}
+
+impl Read for TimedConnection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stream()?.read(buf)
+    }
+}
+
+impl BufRead for TimedConnection {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.stream()?.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.connection.stream.consume(amount)
+    }
+}
@@ -0,0 +1,431 @@
+use std::convert::TryFrom;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::time::{Duration, Instant};
+
+use crate::headers::HeaderMap;
+use crate::structs::{Request, RequestType, Response};
+use crate::transport::Stream;
+use crate::types::{Error, Result, TimeoutPhase};
+use crate::utils::{body_headers, build_headers, default_headers, host_header};
+
+pub(crate) mod chunked;
+pub(crate) mod encoding;
+
+pub use chunked::ChunkedDecoder;
+
+/// Serializes `request` into the exact bytes sent on the wire: the request line, the merged header block and the body.
+/// `default_port` is the port implied by the protocol, it decides whether the port appears in the `Host` header.
+pub fn serialize_request(request: &Request, default_port: usize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    write_request(&mut bytes, request, default_port)?;
+    Ok(bytes)
+}
+
+/// Writes the serialized `request` to a connected stream, reading a streamed body as it goes
+pub fn write_request<W: Write>(stream: &mut W, request: &Request, default_port: usize) -> Result<()> {
+    let body = OutgoingBody::new(request)?;
+
+    let mut defaults = default_headers(host_header(&request.domain, request.port, default_port));
+    if matches!(request.request_type, RequestType::Post | RequestType::Put | RequestType::Patch) {
+        defaults.push(("Accept", String::from("application/json")));
+    }
+    defaults.append(&mut body_headers(request, &body));
+
+    stream.write_all(format!("{} {} HTTP/1.1\r\n{}", request.request_type, request.path, build_headers(defaults, &request.headers)).as_bytes())?;
+    body.write_to(stream)?;
+    stream.flush()?;
+    Ok(())
+}
+
+enum Content {
+    Empty,
+    Bytes(Vec<u8>),
+    Stream(Box<dyn Read + Send>),
+}
+
+/// The body of a request as it is written to the connection, after any compression
+pub struct OutgoingBody {
+    content: Content,
+    content_type: Option<String>,
+    content_encoding: Option<&'static str>,
+    // `None` for a stream of unknown length, which is sent with chunked transfer encoding
+    length: Option<u64>,
+}
+
+impl OutgoingBody {
+    /// Prepares the body of `request`, taking its stream if it has one and compressing it if `Request::compression` is set
+    pub fn new(request: &Request) -> Result<OutgoingBody> {
+        let content_encoding = request.compression.map(|compression| compression.as_str());
+
+        if let Some(stream) = &request.body_stream {
+            let mut reader = stream.take()?;
+            if let Some(compression) = request.compression {
+                reader = encoding::encoder(reader, compression)?;
+            }
+
+            return Ok(OutgoingBody {
+                content: Content::Stream(reader),
+                content_type: Some(stream.content_type().to_string()),
+                content_encoding,
+                length: if content_encoding.is_some() { None } else { stream.length() },
+            });
+        }
+
+        match &request.body {
+            Some((content_type, content)) => {
+                let mut bytes = content.clone();
+                if let Some(compression) = request.compression {
+                    bytes = encoding::compress(bytes, compression)?;
+                }
+
+                Ok(OutgoingBody {
+                    length: Some(bytes.len() as u64),
+                    content: Content::Bytes(bytes),
+                    content_type: Some(content_type.clone()),
+                    content_encoding,
+                })
+            }
+            None => Ok(OutgoingBody { content: Content::Empty, content_type: None, content_encoding: None, length: Some(0) }),
+        }
+    }
+
+    /// Whether the request has a body, empty or not
+    pub fn is_present(&self) -> bool {
+        !matches!(self.content, Content::Empty)
+    }
+
+    /// The length of the body, `None` when it is sent with chunked transfer encoding
+    pub fn length(&self) -> Option<u64> {
+        self.length
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    pub fn content_encoding(&self) -> Option<&str> {
+        self.content_encoding
+    }
+
+    fn write_to<W: Write>(self, stream: &mut W) -> std::io::Result<()> {
+        match self.content {
+            Content::Empty => Ok(()),
+            Content::Bytes(bytes) => stream.write_all(&bytes),
+            Content::Stream(mut reader) if self.length.is_some() => {
+                let expected = self.length.unwrap_or_default();
+                let sent = std::io::copy(&mut reader.by_ref().take(expected), stream)?;
+                if sent < expected {
+                    return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "the request body stream ended before its announced length"));
+                }
+                Ok(())
+            }
+            Content::Stream(mut reader) => {
+                let mut buffer = vec![0; 16 * 1024];
+                loop {
+                    let read = match reader.read(&mut buffer) {
+                        Ok(read) => read,
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                        Err(e) => return Err(e),
+                    };
+                    if read == 0 {
+                        return stream.write_all(b"0\r\n\r\n");
+                    }
+
+                    stream.write_all(format!("{:x}\r\n", read).as_bytes())?;
+                    stream.write_all(&buffer[..read])?;
+                    stream.write_all(b"\r\n")?;
+                }
+            }
+        }
+    }
+}
+
+/// Whether the connection a response was read from can be used for another request.
+/// This is not the case when either side asked for the connection to be closed, when an HTTP/1.0 server did not agree to keep it alive,
+/// when the protocol was switched, or when the body was delimited by the server closing the connection.
+pub fn keeps_alive(response: &Response, request: &Request) -> bool {
+    let has_token = |value: &Option<String>, token: &str| value.as_ref().is_some_and(|value| value.split(',').any(|part| part.trim().eq_ignore_ascii_case(token)));
+
+    if has_token(&request.headers.get_combined("Connection"), "close") {
+        return false;
+    }
+
+    let connection = response.headers.get_combined("Connection");
+    if has_token(&connection, "close") {
+        return false;
+    }
+
+    if response.protocol.as_deref() == Some("HTTP/1.0") && !has_token(&connection, "keep-alive") {
+        return false;
+    }
+
+    let status = response.status.unwrap_or(0);
+    if status == 101 {
+        return false;
+    }
+
+    if matches!(request.request_type, RequestType::Head) || status == 204 || status == 304 || (100..200).contains(&status) {
+        return true;
+    }
+
+    is_chunked(response) || response.headers.contains("Content-Length")
+}
+
+/// Reads the head of the final response, skipping over interim `1xx` responses such as `100 Continue`.
+/// The body is left unread, so it can be streamed with a `BodyDecoder`.
+pub fn read_final_head<R: BufRead>(reader: &mut R) -> Result<Response> {
+    let mut response = Response::new(Vec::new(), read_head(reader)?)?;
+
+    while is_interim(&response) {
+        response = Response::new(Vec::new(), read_head(reader)?)?;
+    }
+
+    Ok(response)
+}
+
+/// Reads the status line and header lines of a response, up to and including the empty line which ends them.
+/// The returned lines have their line endings removed.
+/// If the connection closes before the status line an `Error::Io` with the `UnexpectedEof` kind is returned, which is how a pooled connection closed by the server shows itself.
+pub fn read_head<R: BufRead>(reader: &mut R) -> Result<Vec<String>> {
+    let mut lines: Vec<String> = Vec::new();
+
+    loop {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            if lines.is_empty() {
+                return Err(Error::Io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "the connection closed before the response head was sent")));
+            }
+            return Err(Error::malformed("the connection closed before the response head was complete"));
+        }
+
+        let line = String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string();
+        if line.is_empty() {
+            if lines.is_empty() {
+                // tolerate stray empty lines before the status line (RFC 7230 section 3.5)
+                continue;
+            }
+            return Ok(lines);
+        }
+
+        lines.push(line);
+    }
+}
+
+/// How the end of a response body is found (RFC 7230 section 3.3.3)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Framing {
+    /// The response has no body
+    Empty,
+    /// The body is exactly this many bytes long
+    Length(u64),
+    /// The body is sent in chunks, ending with a zero sized chunk
+    Chunked,
+    /// The body ends when the server closes the connection
+    Close,
+}
+
+// whether the body is sent with the `chunked` transfer coding, which may be named in any of the `Transfer-Encoding` headers
+fn is_chunked(response: &Response) -> bool {
+    response.headers.get_combined("Transfer-Encoding").is_some_and(|encoding| encoding.to_ascii_lowercase().contains("chunked"))
+}
+
+/// Works out how the body of `response` is framed.
+/// Bodies which announce a length longer than `limit` bytes are refused with `Error::BodyTooLarge` before any of them is read.
+pub fn framing(response: &Response, request_type: &RequestType, limit: Option<u64>) -> Result<Framing> {
+    let status = response.status.unwrap_or(0);
+
+    if matches!(request_type, RequestType::Head) || status == 204 || status == 304 || (100..200).contains(&status) {
+        return Ok(Framing::Empty);
+    }
+
+    if is_chunked(response) {
+        return Ok(Framing::Chunked);
+    }
+
+    match response.headers.get_combined("Content-Length") {
+        Some(lengths) => {
+            // repeated lengths are only accepted when they agree (RFC 7230 section 3.3.2)
+            let mut lengths = lengths.split(',').map(|length| length.trim().parse::<u64>());
+            let length = lengths.next().unwrap_or(Ok(0)).map_err(|e| Error::malformed_by("the Content-Length header is not a number", e))?;
+            if lengths.any(|other| other != Ok(length)) {
+                return Err(Error::malformed("the response has several different Content-Length values"));
+            }
+
+            match limit {
+                Some(limit) if length > limit => Err(Error::BodyTooLarge { limit }),
+                _ => Ok(Framing::Length(length)),
+            }
+        }
+        None => Ok(Framing::Close),
+    }
+}
+
+enum Decoding<R: BufRead> {
+    Empty(R),
+    Length(R, u64),
+    Chunked(ChunkedDecoder<R>),
+    Close(R, bool),
+}
+
+/// Reads a response body from `reader` as it arrives, removing the framing.
+/// The decoder stops at the end of the body, so the bytes of a following response stay in `reader`.
+/// A body which ends early fails with `ErrorKind::UnexpectedEof`, and an invalid chunked body with `ErrorKind::InvalidData` or `ErrorKind::InvalidInput`.
+pub struct BodyDecoder<R: BufRead> {
+    decoding: Decoding<R>,
+}
+
+impl<R: BufRead> BodyDecoder<R> {
+    pub fn new(reader: R, framing: Framing) -> BodyDecoder<R> {
+        let decoding = match framing {
+            Framing::Empty => Decoding::Empty(reader),
+            Framing::Length(length) => Decoding::Length(reader, length),
+            Framing::Chunked => Decoding::Chunked(ChunkedDecoder::new(reader)),
+            Framing::Close => Decoding::Close(reader, false),
+        };
+
+        BodyDecoder { decoding }
+    }
+
+    /// Whether the whole body has been read
+    pub fn is_finished(&self) -> bool {
+        match &self.decoding {
+            Decoding::Empty(_) => true,
+            Decoding::Length(_, remaining) => *remaining == 0,
+            Decoding::Chunked(decoder) => decoder.is_finished(),
+            Decoding::Close(_, finished) => *finished,
+        }
+    }
+
+    /// The trailer fields of a chunked body, `None` for bodies with other framing
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        match &self.decoding {
+            Decoding::Chunked(decoder) => Some(decoder.trailers()),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying reader, positioned after the body if it has been read completely
+    pub fn into_inner(self) -> R {
+        match self.decoding {
+            Decoding::Empty(reader) | Decoding::Length(reader, _) | Decoding::Close(reader, _) => reader,
+            Decoding::Chunked(decoder) => decoder.into_inner(),
+        }
+    }
+}
+
+impl<R: BufRead> Read for BodyDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        match &mut self.decoding {
+            Decoding::Empty(_) => Ok(0),
+            Decoding::Length(reader, remaining) => {
+                if *remaining == 0 {
+                    return Ok(0);
+                }
+
+                let wanted = buf.len().min(usize::try_from(*remaining).unwrap_or(usize::MAX));
+                let read = reader.read(&mut buf[..wanted])?;
+                if read == 0 {
+                    return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "the connection closed before the response body was complete"));
+                }
+
+                *remaining -= read as u64;
+                Ok(read)
+            }
+            Decoding::Chunked(decoder) => decoder.read(buf),
+            Decoding::Close(reader, finished) => {
+                let read = reader.read(buf)?;
+                *finished = read == 0;
+                Ok(read)
+            }
+        }
+    }
+}
+
+/// Converts an error raised while reading a response body into the matching `Error`.
+/// Errors which already carry an `Error`, such as `Error::BodyTooLarge` from a streaming body, are passed through unchanged.
+pub fn body_error(error: std::io::Error) -> Error {
+    match error.kind() {
+        std::io::ErrorKind::UnexpectedEof => Error::malformed_by("the connection closed before the response body was complete", error),
+        std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => Error::malformed_by("the response body is not valid", error),
+        _ => error.downcast::<Error>().unwrap_or_else(Error::from),
+    }
+}
+
+/// Shortens `timeout` to the time left before `deadline`.
+/// Once the deadline has passed this fails with a timeout in the `TimeoutPhase::Total` phase.
+pub fn limit(timeout: Option<Duration>, deadline: Option<Instant>) -> Result<Option<Duration>> {
+    // sockets refuse a zero timeout, so the shortest limit is one millisecond
+    let timeout = timeout.map(|timeout| timeout.max(Duration::from_millis(1)));
+
+    match deadline {
+        Some(deadline) => {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Error::timeout(TimeoutPhase::Total, "the request took longer than its total timeout"));
+            }
+            Ok(Some(timeout.map_or(remaining, |timeout| timeout.min(remaining))))
+        }
+        None => Ok(timeout),
+    }
+}
+
+/// Reads from a connection, setting the stream's read timeout before every read which has to wait for the server.
+/// This keeps a slow server from stretching the response past the request's deadline one read at a time.
+pub struct TimedReader<'a> {
+    stream: &'a mut BufReader<Box<dyn Stream>>,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+}
+
+impl<'a> TimedReader<'a> {
+    pub fn new(stream: &'a mut BufReader<Box<dyn Stream>>, timeout: Option<Duration>, deadline: Option<Instant>) -> TimedReader<'a> {
+        TimedReader { stream, timeout, deadline }
+    }
+
+}
+
+impl<'a> Read for TimedReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        arm_read_timeout(self.stream, self.timeout, self.deadline)?;
+        self.stream.read(buf)
+    }
+}
+
+impl<'a> BufRead for TimedReader<'a> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        arm_read_timeout(self.stream, self.timeout, self.deadline)?;
+        self.stream.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.stream.consume(amount)
+    }
+}
+
+/// Sets the read timeout of `stream` before a read which has to wait for the server, bytes already buffered are read without waiting
+pub fn arm_read_timeout(stream: &BufReader<Box<dyn Stream>>, timeout: Option<Duration>, deadline: Option<Instant>) -> std::io::Result<()> {
+    if !stream.buffer().is_empty() {
+        return Ok(());
+    }
+
+    match limit(timeout, deadline) {
+        Ok(timeout) => stream.get_ref().set_read_timeout(timeout),
+        Err(_) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "the request took longer than its total timeout")),
+    }
+}
+
+/// Reports a timeout as the total timeout once the request's deadline has passed
+pub fn past_deadline(error: Error, deadline: Option<Instant>) -> Error {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => error.in_phase(TimeoutPhase::Total),
+        _ => error,
+    }
+}
+
+fn is_interim(response: &Response) -> bool {
+    matches!(response.status, Some(status) if (100..200).contains(&status) && status != 101)
+}
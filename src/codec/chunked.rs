@@ -0,0 +1,146 @@
+use std::convert::TryFrom;
+use std::io::{BufRead, Read};
+
+use crate::headers::HeaderMap;
+use crate::utils::parsers::{is_token, parse_header};
+
+/// The longest chunk size line accepted, including any chunk extensions
+pub const MAX_CHUNK_LINE: usize = 4096;
+/// The most bytes accepted for the whole trailer section
+pub const MAX_TRAILER_SIZE: usize = 16 * 1024;
+
+enum State {
+    // waiting for a chunk size line
+    Size,
+    // in the middle of a chunk, with this many bytes of it left
+    Data(u64),
+    // waiting for the line ending which closes a chunk
+    DataEnd,
+    // reading trailer fields after the last chunk, with this many bytes of trailers read so far
+    Trailers(usize),
+    Done,
+}
+
+/// Decodes a `Transfer-Encoding: chunked` body as it is read (RFC 7230 section 4.1).
+/// Chunk extensions are skipped, and the trailer fields after the last chunk are collected into `trailers`.
+/// Chunk size lines longer than `MAX_CHUNK_LINE` bytes, trailers longer than `MAX_TRAILER_SIZE` bytes and chunk sizes which do not fit in 64 bits
+/// fail with `ErrorKind::InvalidData`, and a body which ends early fails with `ErrorKind::UnexpectedEof`.
+pub struct ChunkedDecoder<R: BufRead> {
+    reader: R,
+    state: State,
+    trailers: HeaderMap,
+}
+
+impl<R: BufRead> ChunkedDecoder<R> {
+    pub fn new(reader: R) -> ChunkedDecoder<R> {
+        ChunkedDecoder { reader, state: State::Size, trailers: HeaderMap::new() }
+    }
+
+    /// Whether the last chunk and the trailers have been read
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// The trailer fields sent after the last chunk, empty until the body has been read to the end
+    pub fn trailers(&self) -> &HeaderMap {
+        &self.trailers
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads one line, without its line ending, failing if it is longer than `limit` bytes
+    fn read_line(&mut self, limit: usize) -> std::io::Result<String> {
+        let mut line = Vec::new();
+
+        loop {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "the connection closed before the chunked body was complete"));
+            }
+
+            let (used, found) = match available.iter().position(|b| *b == b'\n') {
+                Some(end) => (end + 1, true),
+                None => (available.len(), false),
+            };
+            line.extend_from_slice(&available[..used]);
+            self.reader.consume(used);
+
+            if line.len() > limit {
+                return Err(invalid("a line of the chunked body is too long"));
+            }
+            if found {
+                let line = String::from_utf8(line).map_err(|_| invalid("a line of the chunked body is not valid text"))?;
+                return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Read for ChunkedDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            match self.state {
+                State::Size => {
+                    let line = self.read_line(MAX_CHUNK_LINE)?;
+                    let size = parse_chunk_size(&line)?;
+                    self.state = if size == 0 { State::Trailers(0) } else { State::Data(size) };
+                }
+                State::Data(remaining) => {
+                    let wanted = buf.len().min(usize::try_from(remaining).unwrap_or(usize::MAX));
+                    let read = self.reader.read(&mut buf[..wanted])?;
+                    if read == 0 {
+                        return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "the connection closed before the chunked body was complete"));
+                    }
+
+                    let remaining = remaining - read as u64;
+                    self.state = if remaining == 0 { State::DataEnd } else { State::Data(remaining) };
+                    return Ok(read);
+                }
+                State::DataEnd => {
+                    if !self.read_line(2)?.is_empty() {
+                        return Err(invalid("a chunk is longer than its size"));
+                    }
+                    self.state = State::Size;
+                }
+                State::Trailers(size) => {
+                    // only the trailer fields count against the limit, there is always room for the empty line which ends them
+                    let line = self.read_line(MAX_TRAILER_SIZE.saturating_sub(size).max(2))?;
+                    if line.is_empty() {
+                        self.state = State::Done;
+                        continue;
+                    }
+
+                    if !line.split_once(':').is_some_and(|(name, _)| is_token(name)) {
+                        return Err(invalid("a trailer of the chunked body is not a valid header"));
+                    }
+                    self.state = State::Trailers(size + line.len() + 2);
+
+                    let trailer = parse_header(line);
+                    self.trailers.append(trailer.name, trailer.value).map_err(|_| invalid("a trailer of the chunked body is not a valid header"))?;
+                }
+                State::Done => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Parses the hexadecimal size at the start of a chunk size line, skipping any chunk extensions after it
+fn parse_chunk_size(line: &str) -> std::io::Result<u64> {
+    let size = line.split(';').next().unwrap_or_default().trim();
+
+    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid("a chunk size is not a hexadecimal number"));
+    }
+
+    u64::from_str_radix(size, 16).map_err(|_| invalid("a chunk size is too large"))
+}
+
+fn invalid(reason: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, reason)
+}
@@ -0,0 +1,131 @@
+use std::io::{Cursor, Read};
+
+use crate::structs::{Compression, Response};
+
+/// The content codings Curio was built to decode, in order of preference
+const SUPPORTED: &[&str] = &[
+    #[cfg(feature = "brotli")]
+    "br",
+    #[cfg(feature = "gzip")]
+    "gzip",
+    #[cfg(feature = "zstd")]
+    "zstd",
+    #[cfg(feature = "deflate")]
+    "deflate",
+];
+
+/// The value of the `Accept-Encoding` header advertising every supported coding, `None` if Curio was built without any of them
+pub fn accept_encoding() -> Option<String> {
+    if SUPPORTED.is_empty() {
+        None
+    } else {
+        Some(SUPPORTED.join(", "))
+    }
+}
+
+/// Prepares `response` for decoding, if every coding in its `Content-Encoding` header is supported.
+/// The original header is moved to `Response::content_encoding`, and `Content-Length` is removed as it no longer describes the decoded body.
+/// Returns the codings in the order they were applied, or `None` if the body is left as it is.
+pub fn prepare(response: &mut Response) -> Option<Vec<String>> {
+    let header = response.headers.get_combined("Content-Encoding")?;
+    let codings = header.split(',')
+        .map(|coding| coding.trim().to_ascii_lowercase())
+        .filter(|coding| !coding.is_empty() && coding != "identity")
+        .collect::<Vec<String>>();
+
+    if codings.is_empty() || !codings.iter().all(|coding| SUPPORTED.contains(&normalize(coding))) {
+        return None;
+    }
+
+    response.headers.remove("Content-Encoding");
+    response.headers.remove("Content-Length");
+    response.header_count = response.headers.len();
+    response.content_encoding = Some(header);
+    Some(codings)
+}
+
+/// Wraps `reader` in a decoder for each of `codings`, undoing the last coding applied first
+pub fn decoder(reader: Box<dyn Read + Send>, codings: &[String]) -> std::io::Result<Box<dyn Read + Send>> {
+    codings.iter().rev().try_fold(reader, |reader, coding| -> std::io::Result<Box<dyn Read + Send>> {
+        Ok(match normalize(coding) {
+            #[cfg(feature = "gzip")]
+            "gzip" => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            #[cfg(feature = "deflate")]
+            "deflate" => deflate_decoder(reader),
+            #[cfg(feature = "brotli")]
+            "br" => Box::new(brotli::Decompressor::new(reader, 4096)),
+            #[cfg(feature = "zstd")]
+            "zstd" => Box::new(zstd::stream::read::Decoder::new(reader)?),
+            _ => reader,
+        })
+    })
+}
+
+/// Wraps `reader` in an encoder which compresses what it reads with `compression`
+#[cfg_attr(not(any(feature = "gzip", feature = "deflate", feature = "zstd")), allow(unused_variables))]
+pub fn encoder(reader: Box<dyn Read + Send>, compression: Compression) -> std::io::Result<Box<dyn Read + Send>> {
+    match compression {
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(flate2::read::GzEncoder::new(reader, flate2::Compression::default()))),
+        #[cfg(feature = "deflate")]
+        Compression::Deflate => Ok(Box::new(flate2::read::ZlibEncoder::new(reader, flate2::Compression::default()))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Box::new(zstd::stream::read::Encoder::new(reader, 0)?)),
+    }
+}
+
+/// Compresses a buffered request body with `compression`
+pub fn compress(bytes: Vec<u8>, compression: Compression) -> std::io::Result<Vec<u8>> {
+    let mut compressed = Vec::new();
+    encoder(Box::new(Cursor::new(bytes)), compression)?.read_to_end(&mut compressed)?;
+    Ok(compressed)
+}
+
+// `x-gzip` is an alias of `gzip` (RFC 7230 section 4.2.3)
+fn normalize(coding: &str) -> &str {
+    match coding {
+        "x-gzip" => "gzip",
+        coding => coding,
+    }
+}
+
+/// HTTP's `deflate` is a zlib stream, but some servers send raw deflate data, which is told apart by the zlib header.
+/// The header is only looked at on the first read, so a streaming body does not wait for the server when it is created.
+#[cfg(feature = "deflate")]
+enum DeflateDecoder {
+    Pending(Option<std::io::BufReader<Box<dyn Read + Send>>>),
+    Zlib(flate2::read::ZlibDecoder<std::io::BufReader<Box<dyn Read + Send>>>),
+    Raw(flate2::read::DeflateDecoder<std::io::BufReader<Box<dyn Read + Send>>>),
+}
+
+#[cfg(feature = "deflate")]
+fn deflate_decoder(reader: Box<dyn Read + Send>) -> Box<dyn Read + Send> {
+    Box::new(DeflateDecoder::Pending(Some(std::io::BufReader::new(reader))))
+}
+
+#[cfg(feature = "deflate")]
+impl Read for DeflateDecoder {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::io::BufRead;
+
+        if let DeflateDecoder::Pending(pending) = self {
+            let is_zlib = match pending.as_mut().map(|reader| reader.fill_buf()).transpose()? {
+                Some([cmf, flg, ..]) => cmf & 0x0F == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+                _ => true,
+            };
+            let reader = pending.take().expect("a pending deflate decoder always holds its reader");
+
+            *self = if is_zlib {
+                DeflateDecoder::Zlib(flate2::read::ZlibDecoder::new(reader))
+            } else {
+                DeflateDecoder::Raw(flate2::read::DeflateDecoder::new(reader))
+            };
+        }
+
+        match self {
+            DeflateDecoder::Zlib(decoder) => decoder.read(buf),
+            DeflateDecoder::Raw(decoder) => decoder.read(buf),
+            DeflateDecoder::Pending(_) => unreachable!("the deflate decoder was chosen above"),
+        }
+    }
+}
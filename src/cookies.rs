@@ -0,0 +1,404 @@
+//! Cookie storage for `Client` (RFC 6265).
+//!
+//! A `Client` keeps the cookies set by the servers it talks to in its `CookieJar`, and sends them back with every later request they match:
+//! ```no_run
+//! # use curio::prelude::*;
+//! # use curio::structs::Client;
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut client = Client::new();
+//!     client.send(Request::post("https://example.com/login").set_body(&PostData::from_tuple(vec!(("user", "curio")))))?;
+//!
+//!     // the session cookie set by the login is sent along automatically
+//!     let profile = client.send(&Request::get("https://example.com/profile"))?;
+//!     println!("{}", profile.text());
+//!
+//!     for cookie in client.cookies().cookies() {
+//!         println!("{}={} for {}{}", cookie.name, cookie.value, cookie.domain, cookie.path);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Without a public suffix list Curio can not tell which domains are shared between unrelated sites, so it only refuses `Domain` attributes
+//! naming a top level domain, and treats hosts as the same site when the last two labels of their names match.
+//!
+//! Jars can be saved to and loaded from the Netscape `cookies.txt` format shared with curl and wget (`CookieJar::save_netscape`),
+//! or a JSON format which keeps every attribute when Curio is built with the `json` feature (`CookieJar::save_json`).
+
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::structs::{Cookie, HTTPProtocol, Request, RequestType, Response};
+use crate::utils::parsers;
+
+mod persist;
+
+/// The `SameSite` attribute of a cookie, which limits sending it with requests made on behalf of other sites
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    /// Only sent with same-site requests
+    Strict,
+    /// Sent with same-site requests, and with cross-site requests which use a safe method such as GET
+    Lax,
+    /// Sent with every request, which the cookie has to be `Secure` for
+    None,
+}
+
+impl SameSite {
+    /// Reads the value of a `SameSite` attribute, ignoring case. Unknown values are treated as if the attribute was missing.
+    pub fn parse(value: &str) -> Option<SameSite> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "strict" => Some(SameSite::Strict),
+            "lax" => Some(SameSite::Lax),
+            "none" => Some(SameSite::None),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// A cookie kept in a `CookieJar`, with its attributes resolved against the request which set it
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    /// The host the cookie belongs to, in lowercase and without a leading dot
+    pub domain: String,
+    /// Whether the cookie is only sent to `domain` itself, as it was set without a `Domain` attribute
+    pub host_only: bool,
+    /// The path the cookie is sent to, along with every path below it
+    pub path: String,
+    /// Whether the cookie is only sent over HTTPS
+    pub secure: bool,
+    /// Whether the cookie is hidden from non-HTTP APIs, see `CookieJar::matching_non_http`
+    pub http_only: bool,
+    /// The `SameSite` attribute, `None` if the cookie did not set one
+    pub same_site: Option<SameSite>,
+    /// When the cookie expires, `None` for a session cookie which lasts as long as the jar
+    pub expires: Option<SystemTime>,
+    /// When the cookie was first stored, cookies with paths of the same length are sent oldest first
+    pub created: SystemTime,
+}
+
+impl StoredCookie {
+    /// Whether the cookie has expired at `now`
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+/// Stores cookies from responses and picks the ones to send with each request, following RFC 6265 and the `SameSite` and cookie prefix rules of its successor draft.
+/// Clones share the same cookies, so one jar can be used by several clients.
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+    cookies: Arc<Mutex<Vec<StoredCookie>>>,
+}
+
+/// The request a cookie is stored from or sent with
+struct Target {
+    secure: bool,
+    host: String,
+    path: String,
+    // whether the request is made through HTTP, rather than a non-HTTP API such as a script
+    http: bool,
+    // whether the request is made on behalf of the site it is sent to
+    same_site: bool,
+    // whether the request uses a method which can be sent cross-site with `SameSite=Lax` cookies
+    safe_method: bool,
+}
+
+impl CookieJar {
+    pub fn new() -> CookieJar {
+        CookieJar::default()
+    }
+
+    /// Stores `cookie` as if it was set by a response from `url`, returning whether it was accepted.
+    /// Cookies are refused when their `Domain` does not cover the host of `url`, when they are `Secure` but `url` is not HTTPS,
+    /// and when they break the rules of the `__Secure-` and `__Host-` prefixes. A cookie which has already expired removes the cookie it replaces.
+    pub fn store(&self, url: &str, cookie: &Cookie) -> bool {
+        match Target::from_url(url, true) {
+            Some(target) => self.store_for(&target, cookie),
+            None => false,
+        }
+    }
+
+    /// Stores `cookie` like `store`, but on behalf of a non-HTTP API, which may neither set `HttpOnly` cookies nor replace them
+    pub fn store_non_http(&self, url: &str, cookie: &Cookie) -> bool {
+        match Target::from_url(url, false) {
+            Some(target) => self.store_for(&target, cookie),
+            None => false,
+        }
+    }
+
+    /// Adds a cookie which was stored earlier, replacing any cookie with the same name, domain and path
+    pub fn insert(&self, cookie: StoredCookie) {
+        let mut cookies = self.lock();
+        cookies.retain(|stored| !stored.same_identity(&cookie));
+        cookies.push(cookie);
+    }
+
+    /// The cookies sent with a request to `url`, in the order they are sent
+    pub fn matching(&self, url: &str) -> Vec<StoredCookie> {
+        Target::from_url(url, true).map(|target| self.matching_for(&target)).unwrap_or_default()
+    }
+
+    /// The cookies a non-HTTP API may read for `url`, which leaves out `HttpOnly` cookies
+    pub fn matching_non_http(&self, url: &str) -> Vec<StoredCookie> {
+        Target::from_url(url, false).map(|target| self.matching_for(&target)).unwrap_or_default()
+    }
+
+    /// The value of the `Cookie` header sent with a request to `url`, `None` if no cookie matches
+    pub fn header(&self, url: &str) -> Option<String> {
+        cookie_header(&self.matching(url))
+    }
+
+    /// Every cookie in the jar which has not expired
+    pub fn cookies(&self) -> Vec<StoredCookie> {
+        let now = SystemTime::now();
+        let mut cookies = self.lock();
+        cookies.retain(|cookie| !cookie.is_expired(now));
+        cookies.clone()
+    }
+
+    /// Removes the cookie with this name, domain and path, returning it if there was one
+    pub fn remove(&self, name: &str, domain: &str, path: &str) -> Option<StoredCookie> {
+        let mut cookies = self.lock();
+        let index = cookies.iter().position(|cookie| cookie.name == name && cookie.domain.eq_ignore_ascii_case(domain.trim_start_matches('.')) && cookie.path == path)?;
+        Some(cookies.remove(index))
+    }
+
+    /// Removes every cookie
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Removes the cookies without an expiry, which only last until the end of the session, for example after loading the cookies saved by an earlier run
+    pub fn clear_session_cookies(&self) {
+        self.lock().retain(|cookie| cookie.expires.is_some());
+    }
+
+    /// The number of cookies which have not expired
+    pub fn len(&self) -> usize {
+        self.cookies().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stores the cookies set by `response`, which answered `request`
+    pub(crate) fn store_response(&self, request: &Request, response: &Response) {
+        let target = Target::from_request(request);
+        for cookie in &response.cookies {
+            self.store_for(&target, cookie);
+        }
+    }
+
+    /// The value of the `Cookie` header for `request`
+    pub(crate) fn header_for(&self, request: &Request) -> Option<String> {
+        cookie_header(&self.matching_for(&Target::from_request(request)))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<StoredCookie>> {
+        self.cookies.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The storage model of RFC 6265 section 5.3
+    fn store_for(&self, target: &Target, cookie: &Cookie) -> bool {
+        let now = SystemTime::now();
+        if cookie.name.is_empty() && cookie.value.is_empty() {
+            return false;
+        }
+
+        // Max-Age takes precedence over Expires
+        let expires = match cookie.max_age {
+            Some(max_age) if max_age <= 0 => Some(now),
+            // RFC 6265 section 5.2.2 caps the expiry at the latest date that can be represented, rather than making the cookie a session cookie
+            Some(max_age) => Some(now.checked_add(Duration::from_secs(max_age as u64)).map_or_else(latest_expiry, |expires| expires.min(latest_expiry()))),
+            None => cookie.expires,
+        };
+
+        let domain = cookie.domain.as_deref().map(|domain| domain.trim().trim_start_matches('.').to_ascii_lowercase()).filter(|domain| !domain.is_empty());
+        let (domain, host_only) = match domain {
+            // a domain without a dot is a top level domain, which would send the cookie to unrelated sites
+            Some(domain) if domain == target.host => (domain, false),
+            Some(domain) if domain_matches(&target.host, &domain) && domain.contains('.') => (domain, false),
+            Some(_) => return false,
+            None => (target.host.clone(), true),
+        };
+
+        let path = match cookie.path.as_deref() {
+            Some(path) if path.starts_with('/') => path.to_string(),
+            _ => default_path(&target.path),
+        };
+
+        let same_site = cookie.same_site;
+        let prefix_broken = (cookie.name.starts_with("__Secure-") && !cookie.secure)
+            || (cookie.name.starts_with("__Host-") && (!cookie.secure || !host_only || path != "/"));
+
+        if (cookie.secure && !target.secure)
+            || (cookie.http_only && !target.http)
+            || (same_site == Some(SameSite::None) && !cookie.secure)
+            || (!target.same_site && matches!(same_site, Some(SameSite::Strict) | Some(SameSite::Lax)) && !target.safe_method)
+            || prefix_broken {
+            return false;
+        }
+
+        let stored = StoredCookie {
+            name: cookie.name.clone(),
+            value: cookie.value.clone(),
+            domain,
+            host_only,
+            path,
+            secure: cookie.secure,
+            http_only: cookie.http_only,
+            same_site,
+            expires,
+            created: now,
+        };
+
+        let mut cookies = self.lock();
+
+        // an insecure request may not replace or shadow a secure cookie
+        if !target.secure && cookies.iter().any(|existing| existing.secure && existing.name == stored.name
+            && (domain_matches(&existing.domain, &stored.domain) || domain_matches(&stored.domain, &existing.domain))
+            && path_matches(&stored.path, &existing.path)) {
+            return false;
+        }
+
+        let mut stored = stored;
+        if let Some(index) = cookies.iter().position(|existing| existing.same_identity(&stored)) {
+            if cookies[index].http_only && !target.http {
+                return false;
+            }
+            stored.created = cookies.remove(index).created;
+        }
+
+        if stored.is_expired(now) {
+            return false;
+        }
+        cookies.push(stored);
+        true
+    }
+
+    /// The retrieval model of RFC 6265 section 5.4, dropping expired cookies along the way
+    fn matching_for(&self, target: &Target) -> Vec<StoredCookie> {
+        let now = SystemTime::now();
+        let mut cookies = self.lock();
+        cookies.retain(|cookie| !cookie.is_expired(now));
+
+        let mut matching = cookies.iter()
+            .filter(|cookie| if cookie.host_only { cookie.domain == target.host } else { domain_matches(&target.host, &cookie.domain) })
+            .filter(|cookie| path_matches(&target.path, &cookie.path))
+            .filter(|cookie| target.secure || !cookie.secure)
+            .filter(|cookie| target.http || !cookie.http_only)
+            .filter(|cookie| target.same_site || match cookie.same_site {
+                Some(SameSite::Strict) => false,
+                Some(SameSite::Lax) => target.safe_method,
+                _ => true,
+            })
+            .cloned()
+            .collect::<Vec<StoredCookie>>();
+
+        // longer paths first, then the oldest cookies first (the sort is stable, and the jar is in the order cookies were stored)
+        matching.sort_by_key(|cookie| (std::cmp::Reverse(cookie.path.len()), cookie.created));
+        matching
+    }
+}
+
+impl StoredCookie {
+    fn same_identity(&self, other: &StoredCookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+}
+
+impl Target {
+    fn from_url(url: &str, http: bool) -> Option<Target> {
+        let (protocol, host, _, path) = parsers::try_parse_url(url).ok()?;
+        Some(Target {
+            secure: protocol == HTTPProtocol::HTTPS,
+            host: host.to_ascii_lowercase(),
+            path: strip_query(&path).to_string(),
+            http,
+            same_site: true,
+            safe_method: true,
+        })
+    }
+
+    fn from_request(request: &Request) -> Target {
+        let same_site = match &request.site_for_cookies {
+            Some(site) => match parsers::try_parse_url(site) {
+                Ok((protocol, host, _, _)) => protocol == request.protocol && site_of(&host) == site_of(&request.domain),
+                Err(_) => false,
+            },
+            None => true,
+        };
+
+        Target {
+            secure: request.protocol == HTTPProtocol::HTTPS,
+            host: request.domain.to_ascii_lowercase(),
+            path: strip_query(&request.path).to_string(),
+            http: true,
+            same_site,
+            safe_method: matches!(request.request_type, RequestType::Get | RequestType::Head | RequestType::Options),
+        }
+    }
+}
+
+fn cookie_header(cookies: &[StoredCookie]) -> Option<String> {
+    if cookies.is_empty() {
+        return None;
+    }
+
+    let pairs = cookies.iter()
+        .map(|cookie| if cookie.name.is_empty() { cookie.value.clone() } else { format!("{}={}", cookie.name, cookie.value) })
+        .collect::<Vec<String>>();
+    Some(pairs.join("; "))
+}
+
+fn strip_query(path: &str) -> &str {
+    path.split(['?', '#']).next().unwrap_or("/")
+}
+
+/// Whether `host` is `domain` or one of its subdomains (RFC 6265 section 5.1.3)
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || (host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.') && host.parse::<IpAddr>().is_err())
+}
+
+/// Whether `request_path` is `cookie_path` or below it (RFC 6265 section 5.1.4)
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path) && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The directory of the request path, used for cookies without a `Path` attribute (RFC 6265 section 5.1.4)
+fn default_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(0) | None => String::from("/"),
+        Some(last) => request_path[..last].to_string(),
+    }
+}
+
+/// The latest expiry a cookie is stored with, the end of the year 9999 which is the last date a cookie date can name
+fn latest_expiry() -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(253_402_300_799)
+}
+
+/// The site a host belongs to, approximated by the last two labels of its name as Curio has no public suffix list
+fn site_of(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if host.parse::<IpAddr>().is_ok() {
+        return host;
+    }
+
+    let labels = host.rsplitn(3, '.').take(2).collect::<Vec<&str>>();
+    labels.into_iter().rev().collect::<Vec<&str>>().join(".")
+}
@@ -0,0 +1,214 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{CookieJar, StoredCookie};
+use crate::types::Result;
+
+const NETSCAPE_HEADER: &str = "# Netscape HTTP Cookie File\n# This file was generated by Curio, edit at your own risk.\n\n";
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+impl CookieJar {
+    /// Writes the cookies in the Netscape `cookies.txt` format read by curl and wget, one tab separated line per cookie.
+    /// Session cookies are written with an expiry of `0`, and `HttpOnly` cookies have their domain prefixed with `#HttpOnly_` as curl does.
+    /// The format has no room for `SameSite`, use `save_json` to keep it.
+    /// ```no_run
+    /// # use curio::structs::Client;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let client = Client::new();
+    ///     let file = std::fs::File::create("cookies.txt")?;
+    ///     client.cookies().save_netscape(file)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn save_netscape<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(NETSCAPE_HEADER.as_bytes())?;
+
+        for cookie in self.cookies() {
+            let domain = if cookie.host_only { cookie.domain.clone() } else { format!(".{}", cookie.domain) };
+            writeln!(writer, "{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                     if cookie.http_only { HTTP_ONLY_PREFIX } else { "" },
+                     domain,
+                     flag(!cookie.host_only),
+                     cookie.path,
+                     flag(cookie.secure),
+                     cookie.expires.map(unix_seconds).unwrap_or(0),
+                     cookie.name,
+                     cookie.value)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads cookies in the Netscape `cookies.txt` format, as written by `save_netscape`, curl or a browser extension, returning how many were added.
+    /// Cookies with an expiry of `0` become session cookies, cookies which have already expired are skipped, and so are lines which are not valid,
+    /// the same way curl reads the file. Loaded cookies replace those in the jar with the same name, domain and path.
+    /// ```no_run
+    /// # use curio::structs::Client;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let client = Client::new();
+    ///     if let Ok(file) = std::fs::File::open("cookies.txt") {
+    ///         client.cookies().load_netscape(file)?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn load_netscape<R: Read>(&self, reader: R) -> Result<usize> {
+        let now = SystemTime::now();
+        let mut loaded = 0;
+
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            match parse_netscape_line(line.trim_end_matches('\r'), now) {
+                Some(cookie) if !cookie.is_expired(now) => {
+                    self.insert(cookie);
+                    loaded += 1;
+                }
+                _ => {}
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Writes every cookie as a JSON array which keeps all of their attributes, including `SameSite` and when they were created.
+    /// Times are written as seconds since the Unix epoch, and session cookies have an `expires` of `null`.
+    #[cfg(feature = "json")]
+    pub fn save_json<W: Write>(&self, mut writer: W) -> Result<()> {
+        let cookies = self.cookies().iter().map(|cookie| serde_json::json!({
+            "name": cookie.name,
+            "value": cookie.value,
+            "domain": cookie.domain,
+            "host_only": cookie.host_only,
+            "path": cookie.path,
+            "secure": cookie.secure,
+            "http_only": cookie.http_only,
+            "same_site": cookie.same_site.map(|same_site| same_site.as_str()),
+            "expires": cookie.expires.map(unix_seconds),
+            "created": unix_seconds(cookie.created),
+        })).collect::<Vec<serde_json::Value>>();
+
+        serde_json::to_writer_pretty(&mut writer, &cookies).map_err(json_error)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads cookies written by `save_json`, returning how many were added. Cookies which have already expired are skipped,
+    /// and a document which is not an array of cookies with at least a `name`, `value` and `domain` fails with `Error::Json`.
+    #[cfg(feature = "json")]
+    pub fn load_json<R: Read>(&self, reader: R) -> Result<usize> {
+        let document: serde_json::Value = serde_json::from_reader(reader).map_err(json_error)?;
+        let entries = document.as_array().ok_or_else(|| invalid_json("expected an array of cookies"))?;
+
+        let now = SystemTime::now();
+        let mut cookies = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.iter().enumerate() {
+            cookies.push(parse_json_cookie(entry, now).ok_or_else(|| invalid_json(format!("cookie {} is missing a field or has one of the wrong type", index)))?);
+        }
+
+        let mut loaded = 0;
+        for cookie in cookies.into_iter().filter(|cookie| !cookie.is_expired(now)) {
+            self.insert(cookie);
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+}
+
+/// Reads `domain flag path secure expires name value`, where a missing value is empty
+fn parse_netscape_line(line: &str, now: SystemTime) -> Option<StoredCookie> {
+    let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+        Some(line) => (line, true),
+        None if line.starts_with('#') => return None,
+        None => (line, false),
+    };
+
+    let fields = line.split('\t').collect::<Vec<&str>>();
+    if fields.len() < 6 || fields.len() > 7 {
+        return None;
+    }
+
+    let domain = fields[0].trim_start_matches('.').to_ascii_lowercase();
+    let path = fields[2];
+    if domain.is_empty() || !path.starts_with('/') || fields[5].is_empty() {
+        return None;
+    }
+
+    let expires = match fields[4].parse::<u64>().ok()? {
+        0 => None,
+        seconds => Some(from_unix_seconds(seconds)?),
+    };
+
+    Some(StoredCookie {
+        name: fields[5].to_string(),
+        value: fields.get(6).unwrap_or(&"").to_string(),
+        domain,
+        host_only: !parse_flag(fields[1])?,
+        path: path.to_string(),
+        secure: parse_flag(fields[3])?,
+        http_only,
+        same_site: None,
+        expires,
+        created: now,
+    })
+}
+
+#[cfg(feature = "json")]
+fn parse_json_cookie(entry: &serde_json::Value, now: SystemTime) -> Option<StoredCookie> {
+    let text = |field: &str| entry.get(field)?.as_str().map(String::from);
+    let boolean = |field: &str, default: bool| match entry.get(field) {
+        None | Some(serde_json::Value::Null) => Some(default),
+        Some(value) => value.as_bool(),
+    };
+    let time = |field: &str| match entry.get(field) {
+        None | Some(serde_json::Value::Null) => Some(None),
+        Some(value) => value.as_u64().and_then(from_unix_seconds).map(Some),
+    };
+
+    let same_site = match entry.get("same_site") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(value) => Some(super::SameSite::parse(value.as_str()?)?),
+    };
+
+    Some(StoredCookie {
+        name: text("name")?,
+        value: text("value")?,
+        domain: text("domain")?.trim_start_matches('.').to_ascii_lowercase(),
+        host_only: boolean("host_only", false)?,
+        path: text("path").unwrap_or_else(|| String::from("/")),
+        secure: boolean("secure", false)?,
+        http_only: boolean("http_only", false)?,
+        same_site,
+        expires: time("expires")?,
+        created: time("created")?.unwrap_or(now),
+    })
+}
+
+fn flag(value: bool) -> &'static str {
+    if value { "TRUE" } else { "FALSE" }
+}
+
+fn parse_flag(flag: &str) -> Option<bool> {
+    match flag.to_ascii_uppercase().as_str() {
+        "TRUE" => Some(true),
+        "FALSE" => Some(false),
+        _ => None,
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
+
+// `None` for times too far in the future for the platform to represent
+fn from_unix_seconds(seconds: u64) -> Option<SystemTime> {
+    UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
+}
+
+#[cfg(feature = "json")]
+fn json_error(source: serde_json::Error) -> crate::types::Error {
+    crate::types::Error::Json { status: None, excerpt: String::new(), source }
+}
+
+#[cfg(feature = "json")]
+fn invalid_json<S: std::fmt::Display>(reason: S) -> crate::types::Error {
+    json_error(<serde_json::Error as serde::de::Error>::custom(reason))
+}
@@ -0,0 +1,204 @@
+//! Header fields of requests and responses.
+//!
+//! A `HeaderMap` keeps every field in the order it was added or received, with the casing of its name, and finds fields ignoring that casing as HTTP requires.
+//! Fields which may be repeated, such as `Set-Cookie`, `Link` or `Vary`, keep each of their values:
+//! ```
+//! # use curio::prelude::*;
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut headers = HeaderMap::new();
+//!     headers.append("Vary", "Accept-Encoding")?;
+//!     headers.append("vary", "Origin")?;
+//!
+//!     assert_eq!(headers.get("VARY"), Some("Accept-Encoding"));
+//!     assert_eq!(headers.get_all("Vary"), vec!["Accept-Encoding", "Origin"]);
+//!     assert_eq!(headers.get_combined("Vary").as_deref(), Some("Accept-Encoding, Origin"));
+//!     assert!(headers.append("Bad Name", "value").is_err());
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Common headers can also be read into types, which works the same way on the headers of requests and responses.
+//! A value which does not follow the header's grammar is reported as `Error::InvalidHeader`:
+//! ```
+//! # use std::time::Duration;
+//! # use curio::prelude::*;
+//! use curio::headers::{CacheControl, ContentType, Link};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut headers = HeaderMap::new();
+//!     headers.append("Content-Type", "multipart/form-data; boundary=\"abc 123\"")?;
+//!     headers.append("Cache-Control", "no-cache, max-age=60")?;
+//!     headers.append("Link", "<https://api.example.com/items?page=2>; rel=\"next last\"")?;
+//!
+//!     let content_type = headers.typed::<ContentType>()?.unwrap();
+//!     assert_eq!(content_type.boundary(), Some("abc 123"));
+//!
+//!     let cache_control = headers.typed::<CacheControl>()?.unwrap();
+//!     assert!(cache_control.no_cache());
+//!     assert_eq!(cache_control.max_age(), Some(Duration::from_secs(60)));
+//!
+//!     let links = headers.typed::<Link>()?.unwrap();
+//!     assert_eq!(links.find("next").map(|link| link.target.as_str()), Some("https://api.example.com/items?page=2"));
+//!
+//!     headers.insert("Cache-Control", "max-age=soon")?;
+//!     assert!(headers.typed::<CacheControl>().is_err());
+//!     Ok(())
+//! }
+//! ```
+
+use crate::types::{Error, Result};
+use crate::utils::parsers::is_token;
+
+mod typed;
+
+pub use typed::{CacheControl, Challenge, ContentRange, ContentType, Date, ETag, Link, LinkValue, RetryAfter, TypedHeader, WwwAuthenticate};
+
+/// A list of header fields which is searched ignoring the case of names, see the `curio::headers` module
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    pub fn new() -> HeaderMap {
+        HeaderMap::default()
+    }
+
+    /// The first value of the field called `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value)
+    }
+
+    /// Every value of the field called `name`, in the order they were added
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.iter().filter(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value).collect()
+    }
+
+    /// Every value of the field called `name` joined by commas, which is how HTTP combines repeated fields (RFC 7230 section 3.2.2).
+    /// This must not be used for `Set-Cookie`, whose values may contain commas of their own.
+    pub fn get_combined(&self, name: &str) -> Option<String> {
+        let values = self.get_all(name);
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.join(", "))
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Adds a value for `name`, keeping any values it already has.
+    /// Fails with `Error::InvalidHeader` if the name is not a token, or the value contains a line break or another control character.
+    pub fn append<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) -> Result<()> {
+        let (name, value) = (name.into(), value.into());
+        validate(&name, &value)?;
+        self.append_unchecked(name, value);
+        Ok(())
+    }
+
+    /// Sets `name` to `value`, replacing all of its values. The field keeps the position of its first value
+    pub fn insert<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) -> Result<()> {
+        let (name, value) = (name.into(), value.into());
+        validate(&name, &value)?;
+        self.insert_unchecked(name, value);
+        Ok(())
+    }
+
+    /// Removes every value of `name`, returning them in order
+    pub fn remove(&mut self, name: &str) -> Vec<String> {
+        let (removed, kept) = std::mem::take(&mut self.entries).into_iter().partition::<Vec<(String, String)>, _>(|(key, _)| key.eq_ignore_ascii_case(name));
+        self.entries = kept;
+        removed.into_iter().map(|(_, value)| value).collect()
+    }
+
+    /// The number of values, counting each value of a repeated field
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every field as `(name, value)`, in order and with names as they were added
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { entries: self.entries.iter() }
+    }
+
+    /// Checks every field the way `append` does, for headers which were added without being checked, such as with `Request::set_header`
+    pub fn validate(&self) -> Result<()> {
+        self.iter().try_for_each(|(name, value)| validate(name, value))
+    }
+
+    pub(crate) fn append_unchecked<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        self.entries.push((name.into(), trim_whitespace(value.into())));
+    }
+
+    pub(crate) fn insert_unchecked<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        let name = name.into();
+        let value = trim_whitespace(value.into());
+
+        match self.entries.iter().position(|(key, _)| key.eq_ignore_ascii_case(&name)) {
+            Some(first) => {
+                let mut index = 0;
+                self.entries.retain(|(key, _)| {
+                    let keep = index <= first || !key.eq_ignore_ascii_case(&name);
+                    index += 1;
+                    keep
+                });
+                self.entries[first] = (name, value);
+            }
+            None => self.entries.push((name, value)),
+        }
+    }
+}
+
+/// Iterates over the fields of a `HeaderMap`
+pub struct Iter<'a> {
+    entries: std::slice::Iter<'a, (String, String)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<(&'a str, &'a str)> {
+        self.entries.next().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Field names are tokens, and field values may not contain control characters other than tabs (RFC 7230 section 3.2)
+fn validate(name: &str, value: &str) -> Result<()> {
+    let invalid = |reason: &str| Err(Error::InvalidHeader { name: name.to_string(), reason: reason.to_string() });
+
+    if !is_token(name) {
+        return invalid("the name is not a valid token");
+    }
+    if value.contains(['\r', '\n']) {
+        return invalid("the value contains a line break");
+    }
+    if value.chars().any(|c| c.is_ascii_control() && c != '\t') {
+        return invalid("the value contains a control character");
+    }
+    Ok(())
+}
+
+// the whitespace around a value is not part of it (RFC 7230 section 3.2.4)
+fn trim_whitespace(value: String) -> String {
+    let trimmed = value.trim_matches(|c| c == ' ' || c == '\t');
+    if trimmed.len() == value.len() {
+        value
+    } else {
+        trimmed.to_string()
+    }
+}
@@ -0,0 +1,737 @@
+use std::time::{Duration, SystemTime};
+
+use super::HeaderMap;
+use crate::types::{Error, Result};
+use crate::utils::dates::{format_http_date, parse_http_date};
+use crate::utils::parsers::{is_tchar, is_token};
+
+/// A header whose value can be read into a type and written back from it, see the `curio::headers` module
+pub trait TypedHeader: Sized {
+    /// The name of the header
+    const NAME: &'static str;
+
+    /// Whether the header is a comma separated list, whose repeated fields are read as one value
+    const LIST: bool = false;
+
+    /// Reads a value of the header, failing with `Error::InvalidHeader` if it does not follow the header's grammar
+    fn parse(value: &str) -> Result<Self>;
+
+    /// Writes the value of the header
+    fn format(&self) -> String;
+}
+
+impl HeaderMap {
+    /// Reads the header `H`, returning `Ok(None)` when it is missing and `Error::InvalidHeader` when its value can not be parsed.
+    /// Repeated fields of a list header such as `Cache-Control` are read together, for other headers only the first field is read.
+    pub fn typed<H: TypedHeader>(&self) -> Result<Option<H>> {
+        let value = if H::LIST { self.get_combined(H::NAME) } else { self.get(H::NAME).map(String::from) };
+        value.map(|value| H::parse(&value)).transpose()
+    }
+
+    /// Sets the header `H` to the formatted `header`, replacing all of its values
+    pub fn insert_typed<H: TypedHeader>(&mut self, header: &H) -> Result<()> {
+        self.insert(H::NAME, header.format())
+    }
+}
+
+/// The media type of a body and its parameters, such as `text/html; charset=utf-8` (RFC 7231 section 3.1.1.1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType {
+    /// The `type/subtype`, in lowercase
+    pub media_type: String,
+    /// The parameters in the order they were given, with their names in lowercase and their values unquoted
+    pub parameters: Vec<(String, String)>,
+}
+
+impl ContentType {
+    pub fn new<S: Into<String>>(media_type: S) -> ContentType {
+        ContentType { media_type: media_type.into().to_ascii_lowercase(), parameters: Vec::new() }
+    }
+
+    /// Sets a parameter, replacing the value it had
+    pub fn set_parameter<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) -> &mut ContentType {
+        set_parameter(&mut self.parameters, name.into(), value.into());
+        self
+    }
+
+    /// The value of the parameter called `name`, ignoring its case
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        parameter(&self.parameters, name)
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.parameter("charset")
+    }
+
+    /// The boundary between the parts of a `multipart` body
+    pub fn boundary(&self) -> Option<&str> {
+        self.parameter("boundary")
+    }
+}
+
+impl TypedHeader for ContentType {
+    const NAME: &'static str = "Content-Type";
+
+    fn parse(value: &str) -> Result<ContentType> {
+        parse_content_type(value).ok_or_else(|| invalid(Self::NAME, "the value is not a media type followed by parameters"))
+    }
+
+    fn format(&self) -> String {
+        format!("{}{}", self.media_type, format_parameters(&self.parameters))
+    }
+}
+
+/// The directives which control how a response may be cached, such as `no-cache` or `max-age=60` (RFC 7234 section 5.2)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    /// The directives in the order they were given, with their names in lowercase and their arguments unquoted
+    pub directives: Vec<(String, Option<String>)>,
+}
+
+impl CacheControl {
+    pub fn new() -> CacheControl {
+        CacheControl::default()
+    }
+
+    /// Sets a directive, replacing the argument it had
+    pub fn set<N: Into<String>>(&mut self, name: N, argument: Option<&str>) -> &mut CacheControl {
+        let name = name.into().to_ascii_lowercase();
+        let argument = argument.map(String::from);
+        match self.directives.iter_mut().find(|(key, _)| *key == name) {
+            Some(directive) => directive.1 = argument,
+            None => self.directives.push((name, argument)),
+        }
+        self
+    }
+
+    pub fn set_max_age(&mut self, max_age: Duration) -> &mut CacheControl {
+        self.set("max-age", Some(&max_age.as_secs().to_string()))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.directives.iter().any(|(key, _)| key.eq_ignore_ascii_case(name))
+    }
+
+    /// The argument of the directive called `name`, which is `None` if it is missing or has no argument
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.directives.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).and_then(|(_, argument)| argument.as_deref())
+    }
+
+    pub fn no_cache(&self) -> bool {
+        self.contains("no-cache")
+    }
+
+    pub fn no_store(&self) -> bool {
+        self.contains("no-store")
+    }
+
+    pub fn must_revalidate(&self) -> bool {
+        self.contains("must-revalidate")
+    }
+
+    pub fn public(&self) -> bool {
+        self.contains("public")
+    }
+
+    pub fn private(&self) -> bool {
+        self.contains("private")
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.get("max-age").and_then(delta_seconds)
+    }
+
+    /// The `max-age` for shared caches, such as proxies
+    pub fn s_maxage(&self) -> Option<Duration> {
+        self.get("s-maxage").and_then(delta_seconds)
+    }
+}
+
+impl TypedHeader for CacheControl {
+    const NAME: &'static str = "Cache-Control";
+    const LIST: bool = true;
+
+    fn parse(value: &str) -> Result<CacheControl> {
+        let directives = list(value, |cursor| {
+            let name = cursor.token()?.to_ascii_lowercase();
+            let argument = if cursor.eat('=') { Some(cursor.value()?) } else { None };
+            Some((name, argument))
+        }).ok_or_else(|| invalid(Self::NAME, "the value is not a list of directives"))?;
+
+        if directives.is_empty() {
+            return Err(invalid(Self::NAME, "the value has no directives"));
+        }
+        for (name, argument) in &directives {
+            // these take a number of seconds, which is optional for max-stale
+            let numeric = matches!(name.as_str(), "max-age" | "s-maxage" | "min-fresh") || (name == "max-stale" && argument.is_some());
+            if numeric && argument.as_deref().and_then(delta_seconds).is_none() {
+                return Err(invalid(Self::NAME, format!("the {} directive is not a number of seconds", name)));
+            }
+        }
+        Ok(CacheControl { directives })
+    }
+
+    fn format(&self) -> String {
+        self.directives.iter().map(|(name, argument)| match argument {
+            Some(argument) => format!("{}={}", name, token_or_quoted(argument)),
+            None => name.clone(),
+        }).collect::<Vec<String>>().join(", ")
+    }
+}
+
+/// An entity tag which identifies one version of a resource, such as `"33a64df5"` or the weak `W/"0815"` (RFC 7232 section 2.3)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag {
+    /// The tag without its quotes
+    pub tag: String,
+    pub weak: bool,
+}
+
+impl ETag {
+    pub fn strong<S: Into<String>>(tag: S) -> ETag {
+        ETag { tag: tag.into(), weak: false }
+    }
+
+    pub fn weak<S: Into<String>>(tag: S) -> ETag {
+        ETag { tag: tag.into(), weak: true }
+    }
+
+    /// Both tags are strong and the same, as `If-Match` and range requests compare them
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// The tags are the same whether or not they are weak, as `If-None-Match` compares them
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl TypedHeader for ETag {
+    const NAME: &'static str = "ETag";
+
+    fn parse(value: &str) -> Result<ETag> {
+        let value = value.trim_matches(|c| c == ' ' || c == '\t');
+        let (weak, quoted) = match value.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, value),
+        };
+
+        // etagc = %x21 / %x23-7E / obs-text
+        match quoted.strip_prefix('"').and_then(|quoted| quoted.strip_suffix('"')) {
+            Some(tag) if tag.chars().all(|c| c == '!' || ('#'..='~').contains(&c) || !c.is_ascii()) => Ok(ETag { tag: tag.to_string(), weak }),
+            _ => Err(invalid(Self::NAME, "the value is not a quoted entity tag")),
+        }
+    }
+
+    fn format(&self) -> String {
+        format!("{}\"{}\"", if self.weak { "W/" } else { "" }, self.tag)
+    }
+}
+
+/// How long to wait before making another request, as a number of seconds or a date (RFC 7231 section 7.1.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAfter {
+    Delay(Duration),
+    Date(SystemTime),
+}
+
+impl RetryAfter {
+    /// How long to wait from now, which is zero once the date has passed
+    pub fn delay(&self) -> Duration {
+        match self {
+            RetryAfter::Delay(delay) => *delay,
+            RetryAfter::Date(date) => date.duration_since(SystemTime::now()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TypedHeader for RetryAfter {
+    const NAME: &'static str = "Retry-After";
+
+    fn parse(value: &str) -> Result<RetryAfter> {
+        let value = value.trim_matches(|c| c == ' ' || c == '\t');
+        if let Some(delay) = delta_seconds(value) {
+            return Ok(RetryAfter::Delay(delay));
+        }
+        parse_http_date(value).map(RetryAfter::Date).ok_or_else(|| invalid(Self::NAME, "the value is neither a number of seconds nor an HTTP date"))
+    }
+
+    fn format(&self) -> String {
+        match self {
+            RetryAfter::Delay(delay) => delay.as_secs().to_string(),
+            RetryAfter::Date(date) => format_http_date(*date),
+        }
+    }
+}
+
+/// Which part of a representation a `206 Partial Content` response carries, such as `bytes 0-499/1234`,
+/// or how long the representation is when a `416 Range Not Satisfiable` response carries none of it, as `bytes */1234` (RFC 7233 section 4.2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentRange {
+    /// The unit of the range, which is `bytes` for every range defined by HTTP
+    pub unit: String,
+    /// The first and last positions of the range, both included
+    pub range: Option<(u64, u64)>,
+    /// The length of the whole representation, which is `None` when the server does not know it
+    pub complete_length: Option<u64>,
+}
+
+impl ContentRange {
+    pub fn bytes(first: u64, last: u64, complete_length: Option<u64>) -> ContentRange {
+        ContentRange { unit: String::from("bytes"), range: Some((first, last)), complete_length }
+    }
+
+    /// The range of a `416 Range Not Satisfiable` response
+    pub fn unsatisfied(complete_length: u64) -> ContentRange {
+        ContentRange { unit: String::from("bytes"), range: None, complete_length: Some(complete_length) }
+    }
+}
+
+impl TypedHeader for ContentRange {
+    const NAME: &'static str = "Content-Range";
+
+    fn parse(value: &str) -> Result<ContentRange> {
+        let malformed = || invalid(Self::NAME, "the value is not a unit followed by a range and a length");
+        let value = value.trim_matches(|c| c == ' ' || c == '\t');
+
+        let (unit, rest) = value.split_once(' ').filter(|(unit, _)| is_token(unit)).ok_or_else(malformed)?;
+        let (range, length) = rest.split_once('/').ok_or_else(malformed)?;
+        let number = |digits: &str| if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) { digits.parse::<u64>().ok() } else { None };
+
+        let complete_length = match length {
+            "*" => None,
+            length => Some(number(length).ok_or_else(malformed)?),
+        };
+        let range = match range {
+            "*" if complete_length.is_some() => None,
+            range => {
+                let (first, last) = range.split_once('-').ok_or_else(malformed)?;
+                Some((number(first).ok_or_else(malformed)?, number(last).ok_or_else(malformed)?))
+            }
+        };
+
+        if let Some((first, last)) = range {
+            if first > last || complete_length.is_some_and(|length| last >= length) {
+                return Err(invalid(Self::NAME, format!("the range {}-{} is not inside the representation", first, last)));
+            }
+        }
+        Ok(ContentRange { unit: unit.to_ascii_lowercase(), range, complete_length })
+    }
+
+    fn format(&self) -> String {
+        let range = self.range.map(|(first, last)| format!("{}-{}", first, last)).unwrap_or_else(|| String::from("*"));
+        let length = self.complete_length.map(|length| length.to_string()).unwrap_or_else(|| String::from("*"));
+        format!("{} {}/{}", self.unit, range, length)
+    }
+}
+
+/// Links from a resource to others, such as the pages of a paginated API (RFC 8288 section 3)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Link {
+    pub links: Vec<LinkValue>,
+}
+
+impl Link {
+    pub fn new() -> Link {
+        Link::default()
+    }
+
+    pub fn push(&mut self, link: LinkValue) -> &mut Link {
+        self.links.push(link);
+        self
+    }
+
+    /// The first link with the relation `rel`, such as `next`
+    pub fn find(&self, rel: &str) -> Option<&LinkValue> {
+        self.links.iter().find(|link| link.has_rel(rel))
+    }
+}
+
+/// One link of a `Link` header, which is a target and the parameters describing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkValue {
+    /// The URI reference between the angle brackets, which may be relative to the URL of the request
+    pub target: String,
+    /// The parameters in the order they were given, with their names in lowercase and their values unquoted
+    pub parameters: Vec<(String, String)>,
+}
+
+impl LinkValue {
+    pub fn new<S: Into<String>>(target: S) -> LinkValue {
+        LinkValue { target: target.into(), parameters: Vec::new() }
+    }
+
+    /// Sets a parameter, replacing the value it had
+    pub fn set_parameter<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) -> &mut LinkValue {
+        set_parameter(&mut self.parameters, name.into(), value.into());
+        self
+    }
+
+    /// The value of the parameter called `name`, ignoring its case
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        parameter(&self.parameters, name)
+    }
+
+    /// The relations of the link, which `rel` separates by spaces
+    pub fn rels(&self) -> Vec<&str> {
+        self.parameter("rel").map(|rel| rel.split_ascii_whitespace().collect()).unwrap_or_default()
+    }
+
+    /// Whether the link has the relation `rel`, which is compared ignoring case
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rels().iter().any(|candidate| candidate.eq_ignore_ascii_case(rel))
+    }
+}
+
+impl TypedHeader for Link {
+    const NAME: &'static str = "Link";
+    const LIST: bool = true;
+
+    fn parse(value: &str) -> Result<Link> {
+        let links = list(value, |cursor| {
+            if !cursor.eat('<') {
+                return None;
+            }
+            let target = cursor.take_while(|c| c != '>' && !c.is_control());
+            if !cursor.eat('>') {
+                return None;
+            }
+            Some(LinkValue { target: target.to_string(), parameters: cursor.parameters(true)? })
+        });
+        links.map(|links| Link { links }).ok_or_else(|| invalid(Self::NAME, "the value is not a list of links in angle brackets followed by parameters"))
+    }
+
+    fn format(&self) -> String {
+        self.links.iter().map(|link| format!("<{}>{}", link.target, format_parameters(&link.parameters))).collect::<Vec<String>>().join(", ")
+    }
+}
+
+/// The challenges a server accepts credentials for, such as `Basic realm="api"` (RFC 7235 section 4.1)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WwwAuthenticate {
+    pub challenges: Vec<Challenge>,
+}
+
+impl WwwAuthenticate {
+    pub fn new() -> WwwAuthenticate {
+        WwwAuthenticate::default()
+    }
+
+    pub fn push(&mut self, challenge: Challenge) -> &mut WwwAuthenticate {
+        self.challenges.push(challenge);
+        self
+    }
+
+    /// The first challenge for the authentication scheme `scheme`, which is compared ignoring case
+    pub fn find(&self, scheme: &str) -> Option<&Challenge> {
+        self.challenges.iter().find(|challenge| challenge.scheme.eq_ignore_ascii_case(scheme))
+    }
+}
+
+/// One challenge of a `WWW-Authenticate` header, which is an authentication scheme followed by either a token68 or parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub scheme: String,
+    pub token68: Option<String>,
+    /// The parameters in the order they were given, with their names in lowercase and their values unquoted
+    pub parameters: Vec<(String, String)>,
+}
+
+impl Challenge {
+    pub fn new<S: Into<String>>(scheme: S) -> Challenge {
+        Challenge { scheme: scheme.into(), token68: None, parameters: Vec::new() }
+    }
+
+    /// Sets a parameter, replacing the value it had
+    pub fn set_parameter<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) -> &mut Challenge {
+        set_parameter(&mut self.parameters, name.into(), value.into());
+        self
+    }
+
+    /// The value of the parameter called `name`, ignoring its case
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        parameter(&self.parameters, name)
+    }
+
+    /// The protection space the credentials are for
+    pub fn realm(&self) -> Option<&str> {
+        self.parameter("realm")
+    }
+}
+
+impl TypedHeader for WwwAuthenticate {
+    const NAME: &'static str = "WWW-Authenticate";
+    const LIST: bool = true;
+
+    fn parse(value: &str) -> Result<WwwAuthenticate> {
+        parse_challenges(value).map(|challenges| WwwAuthenticate { challenges }).ok_or_else(|| invalid(Self::NAME, "the value is not a list of challenges"))
+    }
+
+    fn format(&self) -> String {
+        self.challenges.iter().map(|challenge| {
+            let mut formatted = challenge.scheme.clone();
+            if let Some(token68) = &challenge.token68 {
+                formatted.push(' ');
+                formatted.push_str(token68);
+            }
+            // parameters are always quoted, as some clients only read a quoted realm
+            let parameters = challenge.parameters.iter().map(|(name, value)| format!("{}={}", name, quote(value))).collect::<Vec<String>>();
+            if !parameters.is_empty() {
+                formatted.push(' ');
+                formatted.push_str(&parameters.join(", "));
+            }
+            formatted
+        }).collect::<Vec<String>>().join(", ")
+    }
+}
+
+/// The time a message was created (RFC 7231 section 7.1.1.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date(pub SystemTime);
+
+impl Date {
+    pub fn now() -> Date {
+        Date(SystemTime::now())
+    }
+}
+
+impl TypedHeader for Date {
+    const NAME: &'static str = "Date";
+
+    fn parse(value: &str) -> Result<Date> {
+        parse_http_date(value.trim_matches(|c| c == ' ' || c == '\t')).map(Date).ok_or_else(|| invalid(Self::NAME, "the value is not an HTTP date"))
+    }
+
+    fn format(&self) -> String {
+        format_http_date(self.0)
+    }
+}
+
+// media-type = type "/" subtype *( OWS ";" OWS parameter )
+fn parse_content_type(value: &str) -> Option<ContentType> {
+    let mut cursor = Cursor::new(value.trim_matches(|c| c == ' ' || c == '\t'));
+    let kind = cursor.token()?;
+    if !cursor.eat('/') {
+        return None;
+    }
+    let subtype = cursor.token()?;
+    let parameters = cursor.parameters(false)?;
+
+    if !cursor.is_empty() {
+        return None;
+    }
+    Some(ContentType { media_type: format!("{}/{}", kind, subtype).to_ascii_lowercase(), parameters })
+}
+
+/// Challenges are separated by commas, as are their parameters, so a challenge ends where an element does not look like `name=value`:
+/// `challenge = auth-scheme [ 1*SP ( token68 / #auth-param ) ]`
+fn parse_challenges(value: &str) -> Option<Vec<Challenge>> {
+    let mut cursor = Cursor::new(value);
+    let mut challenges = Vec::new();
+
+    loop {
+        cursor.skip_while(|c| c == ' ' || c == '\t' || c == ',');
+        if cursor.is_empty() {
+            return Some(challenges);
+        }
+        let mut challenge = Challenge::new(cursor.token()?);
+        cursor.skip_whitespace();
+
+        // token68 = 1*( ALPHA / DIGIT / "-" / "." / "_" / "~" / "+" / "/" ) *"="
+        let start = cursor.rest;
+        let token68 = cursor.take_while(|c| c.is_ascii_alphanumeric() || "-._~+/".contains(c));
+        let padding = cursor.take_while(|c| c == '=');
+        cursor.skip_whitespace();
+        if !token68.is_empty() && (cursor.is_empty() || cursor.rest.starts_with(',')) {
+            challenge.token68 = Some(format!("{}{}", token68, padding));
+            challenges.push(challenge);
+            continue;
+        }
+        cursor.rest = start;
+
+        loop {
+            let start = cursor.rest;
+            cursor.skip_while(|c| c == ' ' || c == '\t' || c == ',');
+            let name = match cursor.token() {
+                Some(name) => name,
+                None => break,
+            };
+            cursor.skip_whitespace();
+            if !cursor.eat('=') {
+                // the name is the scheme of the next challenge
+                cursor.rest = start;
+                break;
+            }
+            cursor.skip_whitespace();
+            let value = cursor.value()?;
+            challenge.parameters.push((name.to_ascii_lowercase(), value));
+            cursor.skip_whitespace();
+            if !cursor.is_empty() && !cursor.rest.starts_with(',') {
+                return None;
+            }
+        }
+        challenges.push(challenge);
+    }
+}
+
+/// Reads the elements of a comma separated list, where empty elements are ignored (RFC 7230 section 7)
+fn list<T, F: FnMut(&mut Cursor) -> Option<T>>(value: &str, mut element: F) -> Option<Vec<T>> {
+    let mut cursor = Cursor::new(value);
+    let mut elements = Vec::new();
+
+    loop {
+        cursor.skip_while(|c| c == ' ' || c == '\t' || c == ',');
+        if cursor.is_empty() {
+            return Some(elements);
+        }
+        elements.push(element(&mut cursor)?);
+        cursor.skip_whitespace();
+        if !cursor.is_empty() && !cursor.rest.starts_with(',') {
+            return None;
+        }
+    }
+}
+
+/// Reads the parts of a header value from left to right
+struct Cursor<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(value: &'a str) -> Cursor<'a> {
+        Cursor { rest: value }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> &'a str {
+        let end = self.rest.find(|c| !predicate(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        taken
+    }
+
+    fn skip_while<F: Fn(char) -> bool>(&mut self, predicate: F) {
+        self.take_while(predicate);
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.skip_while(|c| c == ' ' || c == '\t');
+    }
+
+    fn token(&mut self) -> Option<&'a str> {
+        Some(self.take_while(|c| c.is_ascii() && is_tchar(c as u8))).filter(|token| !token.is_empty())
+    }
+
+    /// Reads a quoted string without its quotes and with its backslash escapes removed (RFC 7230 section 3.2.6)
+    fn quoted_string(&mut self) -> Option<String> {
+        let mut chars = self.rest.strip_prefix('"')?.char_indices();
+        let mut value = String::new();
+
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    // past the opening and closing quotes
+                    self.rest = &self.rest[index + 2..];
+                    return Some(value);
+                }
+                '\\' => match chars.next()? {
+                    (_, escaped) if escaped.is_control() && escaped != '\t' => return None,
+                    (_, escaped) => value.push(escaped),
+                },
+                c if c.is_control() && c != '\t' => return None,
+                c => value.push(c),
+            }
+        }
+        None
+    }
+
+    /// Reads a token or a quoted string
+    fn value(&mut self) -> Option<String> {
+        if self.rest.starts_with('"') {
+            self.quoted_string()
+        } else {
+            self.token().map(String::from)
+        }
+    }
+
+    /// Reads `*( OWS ";" OWS name "=" value )`, where the value may be left out when `optional_values` is set
+    fn parameters(&mut self, optional_values: bool) -> Option<Vec<(String, String)>> {
+        let mut parameters = Vec::new();
+
+        loop {
+            let start = self.rest;
+            self.skip_whitespace();
+            if !self.eat(';') {
+                self.rest = start;
+                return Some(parameters);
+            }
+            self.skip_whitespace();
+            let name = self.token()?.to_ascii_lowercase();
+            self.skip_whitespace();
+
+            let value = if self.eat('=') {
+                self.skip_whitespace();
+                self.value()?
+            } else if optional_values {
+                String::new()
+            } else {
+                return None;
+            };
+            parameters.push((name, value));
+        }
+    }
+}
+
+fn parameter<'a>(parameters: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    parameters.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+}
+
+fn set_parameter(parameters: &mut Vec<(String, String)>, name: String, value: String) {
+    let name = name.to_ascii_lowercase();
+    match parameters.iter_mut().find(|(key, _)| *key == name) {
+        Some(parameter) => parameter.1 = value,
+        None => parameters.push((name, value)),
+    }
+}
+
+fn format_parameters(parameters: &[(String, String)]) -> String {
+    parameters.iter().map(|(name, value)| format!("; {}={}", name, token_or_quoted(value))).collect()
+}
+
+fn token_or_quoted(value: &str) -> String {
+    if is_token(value) {
+        value.to_string()
+    } else {
+        quote(value)
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Reads `delta-seconds`, where numbers too large to represent are read as the largest delta caches must accept (RFC 7234 section 1.2.1)
+fn delta_seconds(value: &str) -> Option<Duration> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(Duration::from_secs(value.parse::<u64>().unwrap_or(2147483648)))
+}
+
+fn invalid<S: Into<String>>(name: &str, reason: S) -> Error {
+    Error::InvalidHeader { name: name.to_string(), reason: reason.into() }
+}
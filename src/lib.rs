@@ -8,7 +8,7 @@
 //!
 //! # GET content from a url:
 //! This one is just a simple GET request, no headers necessary here:
-//! ```
+//! ```no_run
 //! # use curio::prelude::*;
 //! fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let response = Request::get("https://example.com//path/to/resource")
@@ -21,7 +21,7 @@
 //!
 //! # POST tuple content to a url:
 //! If the endpoint that you are posting data to supports `application/x-www-form-urlencoded` body structures but you __dont__ want to use a HashMap for whatever reason, this method is the way to go:
-//! ```
+//! ```no_run
 //! # use curio::prelude::*;
 //! fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // lets define a tuple containing key-value pairs.
@@ -30,7 +30,7 @@
 //!         ("timestamp", "Fri, 28 Aug 2020 10:55:44 +0000")
 //!     );
 //!
-//!     // in this line we convert the tuple into a key-value HashMap.
+//!     // in this line we convert the tuple into form fields, which are sent in the same order.
 //!     let post_data = PostData::from_tuple(post_body);
 //!
 //!     // below, we set the destination of the post body using the `post` method,
@@ -47,7 +47,7 @@
 //!
 //! # POST HashMap content to a url:
 //! If the endpoint that you are posting data to supports `application/x-www-form-urlencoded` body structures, the example below should work pretty well for most users:
-//! ```
+//! ```no_run
 //! # use std::collections::HashMap;
 //! # use curio::prelude::*;
 //! fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -56,7 +56,7 @@
 //!     post_body.insert("author", "Altrius");
 //!     post_body.insert("timestamp", "Fri, 28 Aug 2020 10:55:44 +0000");
 //!
-//!     // in this line we convert the HashMap into a key-value HashMap.
+//!     // in this line we convert the HashMap into form fields.
 //!     let post_data = PostData::from_hash_map(post_body);
 //!
 //!     // below, we set the destination of the post body using the `post` method,
@@ -72,7 +72,7 @@
 //! ```
 //! # POST plaintext content to a url:
 //! Does the endpoint you want to POST data to accept plaintext? the example below might work best for this situation:
-//! ```
+//! ```no_run
 //! # use curio::prelude::*;
 //! fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // lets define a string containing the content body.
@@ -94,7 +94,6 @@
 //! }
 //! ```
 
-#[doc(hidden)]
 pub mod types;
 
 #[doc(hidden)]
@@ -103,6 +102,21 @@ pub mod tls;
 #[doc(hidden)]
 pub mod tcp;
 
+#[doc(hidden)]
+pub mod codec;
+#[doc(hidden)]
+pub mod pool;
+
+pub mod transport;
+
+pub mod body;
+
+pub mod headers;
+
+pub mod multipart;
+
+pub mod cookies;
+
 //pub mod client;
 
 pub mod structs;
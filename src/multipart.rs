@@ -0,0 +1,307 @@
+//! `multipart/form-data` request bodies (RFC 7578).
+//!
+//! A `Multipart` form holds text fields, in-memory byte parts and parts read from files or any other `Read` implementor.
+//! The form is streamed while the request is sent, so large files are never loaded into memory:
+//! ```no_run
+//! # use curio::prelude::*;
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mut form = Multipart::new();
+//!     form.add_text("title", "Holiday photos")
+//!         .add_file("photo", "beach.jpg")?
+//!         .add_part("notes", Part::bytes(b"taken at noon".to_vec()).set_file_name("notes.txt").set_content_type("text/plain"));
+//!
+//!     let response = Request::post("https://example.com/albums")
+//!         .set_multipart(form)
+//!         .send()?;
+//!
+//!     println!("{:#?}", response);
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::types::Result;
+
+/// A `multipart/form-data` form, sent with `Request::set_multipart`.
+/// Parts are sent in the order they were added, separated by a boundary which is generated when the form is created.
+#[derive(Debug)]
+pub struct Multipart {
+    boundary: String,
+    parts: Vec<(String, Part)>,
+}
+
+/// A single part of a `Multipart` form, with its own file name, content type and extra headers
+pub struct Part {
+    content: Content,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    headers: Vec<(String, String)>,
+}
+
+enum Content {
+    Bytes(Vec<u8>),
+    Reader(Box<dyn Read + Send>, Option<u64>),
+}
+
+impl Multipart {
+    /// Creates an empty form with a random boundary
+    pub fn new() -> Multipart {
+        Multipart {
+            boundary: generate_boundary(),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Replaces the generated boundary.
+    /// The boundary must not appear anywhere in the content of the parts, and RFC 2046 limits it to 70 characters.
+    pub fn set_boundary<S: Into<String>>(&mut self, boundary: S) -> &mut Multipart {
+        self.boundary = boundary.into();
+        self
+    }
+
+    /// The boundary separating the parts of the form
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Adds a text field
+    pub fn add_text<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) -> &mut Multipart {
+        self.add_part(name, Part::text(value))
+    }
+
+    /// Adds the file at `path` as a part, see `Part::file`
+    pub fn add_file<K: Into<String>, P: AsRef<Path>>(&mut self, name: K, path: P) -> Result<&mut Multipart> {
+        let part = Part::file(path)?;
+        Ok(self.add_part(name, part))
+    }
+
+    /// Adds a part built with one of the `Part` constructors
+    pub fn add_part<K: Into<String>>(&mut self, name: K, part: Part) -> &mut Multipart {
+        self.parts.push((name.into(), part));
+        self
+    }
+
+    /// The value of the `Content-Type` header for this form, including its boundary
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// The length of the encoded form, `None` if a part is read from a stream of unknown length
+    pub fn length(&self) -> Option<u64> {
+        let mut length = self.closing().len() as u64;
+        for (name, part) in &self.parts {
+            length += self.part_head(name, part).len() as u64 + part.length()? + 2;
+        }
+        Some(length)
+    }
+
+    /// Turns the form into a reader which produces the encoded body, reading each part only when it is reached
+    pub fn into_reader(self) -> impl Read + Send {
+        let mut segments: VecDeque<Box<dyn Read + Send>> = VecDeque::new();
+        let closing = self.closing();
+
+        let heads = self.parts.iter().map(|(name, part)| self.part_head(name, part)).collect::<Vec<Vec<u8>>>();
+
+        for (head, (_, part)) in heads.into_iter().zip(self.parts) {
+            segments.push_back(Box::new(Cursor::new(head)));
+            segments.push_back(match part.content {
+                Content::Bytes(bytes) => Box::new(Cursor::new(bytes)),
+                Content::Reader(reader, _) => reader,
+            });
+            segments.push_back(Box::new(Cursor::new(b"\r\n".to_vec())));
+        }
+        segments.push_back(Box::new(Cursor::new(closing)));
+
+        MultipartReader { segments }
+    }
+
+    /// The boundary delimiter, headers and blank line which come before the content of a part
+    fn part_head(&self, name: &str, part: &Part) -> Vec<u8> {
+        let mut head = format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"", self.boundary, escape_quoted(name));
+        if let Some(file_name) = &part.file_name {
+            head.push_str(&format!("; filename=\"{}\"", escape_quoted(file_name)));
+        }
+        head.push_str("\r\n");
+
+        if let Some(content_type) = &part.content_type {
+            head.push_str(&format!("Content-Type: {}\r\n", strip_line_breaks(content_type)));
+        }
+        for (header, value) in &part.headers {
+            head.push_str(&format!("{}: {}\r\n", strip_line_breaks(header), strip_line_breaks(value)));
+        }
+        head.push_str("\r\n");
+        head.into_bytes()
+    }
+
+    fn closing(&self) -> Vec<u8> {
+        format!("--{}--\r\n", self.boundary).into_bytes()
+    }
+}
+
+impl Default for Multipart {
+    fn default() -> Multipart {
+        Multipart::new()
+    }
+}
+
+impl Part {
+    /// A text field, sent without a `Content-Type` so the server treats it as `text/plain`
+    pub fn text<S: Into<String>>(value: S) -> Part {
+        Part::new(Content::Bytes(value.into().into_bytes()), None)
+    }
+
+    /// Binary content held in memory, sent as `application/octet-stream` unless another content type is set
+    pub fn bytes<B: Into<Vec<u8>>>(bytes: B) -> Part {
+        Part::new(Content::Bytes(bytes.into()), Some(String::from("application/octet-stream")))
+    }
+
+    /// Content read from `reader` while the request is sent, as `application/octet-stream` unless another content type is set.
+    /// When `length` is not known the whole request is sent with chunked transfer encoding.
+    pub fn reader<R: Read + Send + 'static>(reader: R, length: Option<u64>) -> Part {
+        Part::new(Content::Reader(Box::new(reader), length), Some(String::from("application/octet-stream")))
+    }
+
+    /// The file at `path`, which is opened straight away but only read while the request is sent.
+    /// The file name is taken from the path and the content type is guessed from its extension.
+    pub fn file<P: AsRef<Path>>(path: P) -> Result<Part> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        let length = file.metadata()?.len();
+
+        let mut part = Part::reader(file, Some(length)).set_content_type(guess_content_type(path));
+        if let Some(file_name) = path.file_name() {
+            part = part.set_file_name(file_name.to_string_lossy());
+        }
+        Ok(part)
+    }
+
+    /// Sets the `filename` sent in the `Content-Disposition` header of the part
+    pub fn set_file_name<S: Into<String>>(mut self, file_name: S) -> Part {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    /// Sets the `Content-Type` header of the part
+    pub fn set_content_type<S: Into<String>>(mut self, content_type: S) -> Part {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Adds an extra header to the part. Line breaks are removed from the name and value, as they would end the header early.
+    pub fn set_header<A: Into<String>>(mut self, key: A, value: A) -> Part {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    fn new(content: Content, content_type: Option<String>) -> Part {
+        Part { content, file_name: None, content_type, headers: Vec::new() }
+    }
+
+    fn length(&self) -> Option<u64> {
+        match &self.content {
+            Content::Bytes(bytes) => Some(bytes.len() as u64),
+            Content::Reader(_, length) => *length,
+        }
+    }
+}
+
+impl std::fmt::Debug for Part {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Part")
+            .field("file_name", &self.file_name)
+            .field("content_type", &self.content_type)
+            .field("headers", &self.headers)
+            .field("length", &self.length())
+            .finish()
+    }
+}
+
+/// Reads the encoded form one segment at a time
+struct MultipartReader {
+    segments: VecDeque<Box<dyn Read + Send>>,
+}
+
+impl Read for MultipartReader {
+    // fills as much of `buf` as the segments allow, so the small heads between parts do not each become a chunk of their own
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut filled = 0;
+
+        while filled < buf.len() {
+            let segment = match self.segments.front_mut() {
+                Some(segment) => segment,
+                None => break,
+            };
+            match segment.read(&mut buf[filled..])? {
+                0 => {
+                    self.segments.pop_front();
+                }
+                read => filled += read,
+            }
+        }
+        Ok(filled)
+    }
+}
+
+/// Generates a boundary from a randomly keyed hash of the time and a counter, so no two forms share one
+fn generate_boundary() -> String {
+    static FORMS: AtomicUsize = AtomicUsize::new(0);
+
+    let count = FORMS.fetch_add(1, Ordering::Relaxed);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos()).unwrap_or_default();
+    let random = |salt: u8| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u8(salt);
+        hasher.write_usize(count);
+        hasher.write_u128(now);
+        hasher.finish()
+    };
+
+    format!("------------------------{:016x}{:016x}", random(0), random(1))
+}
+
+// names and file names are quoted strings, escaped the way browsers escape them (WHATWG HTML, multipart/form-data encoding algorithm)
+fn escape_quoted(value: &str) -> String {
+    value.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn strip_line_breaks(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// Guesses the media type of a file from its extension, falling back to `application/octet-stream`
+fn guess_content_type(path: &Path) -> &'static str {
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+
+    match extension.as_str() {
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "js" | "mjs" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "wasm" => "application/wasm",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
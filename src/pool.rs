@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::structs::{ClientConfig, Connection};
+use crate::transport::Transport;
+use crate::types::{Error, Result, TimeoutPhase};
+
+/// Identifies the connections which can serve a request: the same host and port, over the same protocol
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PoolKey {
+    pub host: String,
+    pub port: usize,
+    pub secure: bool,
+}
+
+impl PoolKey {
+    /// Host names are compared without regard to case, so `Example.com` and `example.com` share their connections
+    pub fn new<S: AsRef<str>>(host: S, port: usize, secure: bool) -> PoolKey {
+        PoolKey { host: host.as_ref().to_ascii_lowercase(), port, secure }
+    }
+}
+
+#[derive(Default)]
+struct HostPool {
+    // connections waiting for a request, the most recently used last
+    idle: Vec<Connection>,
+    // idle connections plus the connections currently checked out
+    open: usize,
+}
+
+/// Keeps connections open between requests so that requests to the same host can skip the TCP and TLS handshakes.
+/// At most `ClientConfig::connection_limit` connections are open to each host at once, and connections which have been idle for longer than
+/// `ClientConfig::idle_timeout` are closed the next time the pool is used.
+/// Clones share the same connections.
+#[derive(Clone, Default)]
+pub struct Pool {
+    hosts: Arc<Mutex<HashMap<PoolKey, HostPool>>>,
+    // signalled whenever a connection goes back to the pool or is closed, waking requests waiting for a free connection
+    released: Arc<Condvar>,
+}
+
+impl Pool {
+    pub fn new() -> Pool {
+        Pool::default()
+    }
+
+    /// Takes an idle connection for `key` out of the pool, or opens a new one through `transport` if there is none.
+    /// When the host already has `connection_limit` connections open, waits until one of them is returned or closed.
+    /// `timeout` limits how long that wait may take, failing with a connect timeout, and how long opening a new connection may take.
+    pub fn checkout(&self, transport: &dyn Transport, key: &PoolKey, config: &ClientConfig, timeout: Option<Duration>) -> Result<Connection> {
+        let started = Instant::now();
+        let mut hosts = self.hosts();
+        loop {
+            evict_idle(&mut hosts, config);
+
+            let host = hosts.entry(key.clone()).or_default();
+            if let Some(connection) = host.idle.pop() {
+                return Ok(connection);
+            }
+            // without reuse there is no limit to enforce
+            if config.connection_limit == 0 || host.open < config.connection_limit as usize {
+                host.open += 1;
+                break;
+            }
+
+            hosts = match timeout {
+                Some(timeout) => match timeout.checked_sub(started.elapsed()).filter(|remaining| !remaining.is_zero()) {
+                    Some(remaining) => self.released.wait_timeout(hosts, remaining).unwrap_or_else(|poisoned| poisoned.into_inner()).0,
+                    None => return Err(Error::timeout(TimeoutPhase::Connect, format!("no connection to {} became free", key.host))),
+                },
+                None => self.released.wait(hosts).unwrap_or_else(|poisoned| poisoned.into_inner()),
+            };
+        }
+        drop(hosts);
+
+        match transport.connect(&key.host, key.port, key.secure, timeout) {
+            Ok(stream) => Ok(Connection {
+                is_secure: key.secure,
+                domain: key.host.clone(),
+                port: key.port,
+                requests: 0,
+                idle_since: Instant::now(),
+                stream: BufReader::new(stream),
+            }),
+            Err(e) => {
+                self.release(key);
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns a connection to the pool after a complete response was read from it.
+    /// The connection is closed instead if `reusable` is false, if connection reuse is disabled, or if the host already has `connection_limit` connections open.
+    pub fn checkin(&self, mut connection: Connection, reusable: bool, config: &ClientConfig) {
+        let key = connection.key();
+        let mut hosts = self.hosts();
+        let host = hosts.entry(key).or_default();
+
+        if reusable && !config.cycle_connections && host.open <= config.connection_limit as usize {
+            connection.idle_since = Instant::now();
+            host.idle.push(connection);
+        } else {
+            host.open = host.open.saturating_sub(1);
+        }
+        self.released.notify_all();
+    }
+
+    /// Closes a connection which failed or can not be reused
+    pub fn discard(&self, connection: Connection) {
+        self.release(&connection.key());
+    }
+
+    /// The number of connections waiting in the pool for a request
+    pub fn idle_count(&self) -> usize {
+        self.hosts().values().map(|host| host.idle.len()).sum()
+    }
+
+    /// Closes every idle connection
+    pub fn clear(&self) {
+        let mut hosts = self.hosts();
+        for host in hosts.values_mut() {
+            host.open -= host.idle.len();
+            host.idle.clear();
+        }
+        hosts.retain(|_, host| host.open > 0);
+        self.released.notify_all();
+    }
+
+    fn release(&self, key: &PoolKey) {
+        if let Some(host) = self.hosts().get_mut(key) {
+            host.open = host.open.saturating_sub(1);
+        }
+        self.released.notify_all();
+    }
+
+    fn hosts(&self) -> MutexGuard<'_, HashMap<PoolKey, HostPool>> {
+        self.hosts.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Closes connections which have been idle for longer than the configured timeout, the server has most likely closed them already
+fn evict_idle(hosts: &mut HashMap<PoolKey, HostPool>, config: &ClientConfig) {
+    for host in hosts.values_mut() {
+        let before = host.idle.len();
+        host.idle.retain(|connection| connection.idle_since.elapsed() < config.idle_timeout);
+        host.open -= before - host.idle.len();
+    }
+    hosts.retain(|_, host| host.open > 0);
+}
@@ -1,2 +1,4 @@
 pub use crate::structs::{Request, Response, PostData};
+pub use crate::headers::HeaderMap;
+pub use crate::multipart::{Multipart, Part};
 //pub use crate::types::{Error};
@@ -1,8 +1,18 @@
 use std::collections::HashMap;
-use std::net::TcpStream;
+use std::io::{BufReader, Read};
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::utils;
 use crate::utils::parsers;
+use crate::codec::{self, Framing, TimedReader};
+use crate::types::{Error, Result, TimeoutPhase};
+use crate::transport::{NetTransport, Stream, Transport};
+use crate::pool::{Pool, PoolKey};
+use crate::body::{BodyReader, BodyStream, StreamingResponse};
+use crate::multipart::Multipart;
+use crate::cookies::{CookieJar, SameSite};
+use crate::headers::{HeaderMap, TypedHeader};
+use std::sync::Arc;
 
 /// Defines the method to be used in the request
 #[derive(Debug, Clone)]
@@ -14,10 +24,12 @@ pub enum RequestType {
     Delete,
     Patch,
     Options,
+    /// Any other method token, such as the WebDAV `PROPFIND`, `MKCOL` or `LOCK` methods (see `Request::custom`)
+    Custom(String),
 }
 
 /// Defines the type of HTTP to be used in the request (TCP/TLS)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum HTTPProtocol {
     HTTP,
     HTTPS,
@@ -41,17 +53,44 @@ pub struct Request {
     /// If a server requests we use HTTPS, we will automatically switch over anyway, no fiddling needed
     pub protocol: HTTPProtocol,
     /// Not all requests have a body, this is an optional field containing a tuple value of both the encoding, and the body content
-    pub body: Option<(String, String)>,
-    /// This stores the values of each header you set within the request. This is the first step to authenticating a request
-    pub headers: HashMap<String, String>,
+    pub body: Option<(String, Vec<u8>)>,
+    /// This stores the values of each header you set within the request, in the order they are sent. This is the first step to authenticating a request
+    pub headers: HeaderMap,
     /// the number of headers this request stores in `headers`
     pub header_count: usize,
+    /// Timeouts for this request, any which are not set fall back to the client's `ClientConfig::timeouts`
+    pub timeouts: Timeouts,
+    /// How the body is compressed before it is sent, `None` sends it as it is
+    pub compression: Option<Compression>,
+    /// Whether the body is sent again uncompressed when the server answers a compressed body with `415 Unsupported Media Type`
+    pub retry_uncompressed: bool,
+    /// A body which is read from a stream as it is sent, instead of `body`
+    pub body_stream: Option<BodyStream>,
+    /// The URL of the site the request is made on behalf of, which decides whether `SameSite` cookies are sent. `None` treats the request as same-site
+    pub site_for_cookies: Option<String>,
+    // the charset of a body given to `set_body` which could not be encoded, reported when the request is sent
+    unsupported_charset: Option<String>,
+}
+
+/// The content codings a request body can be compressed with, each needs the Curio feature of the same name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    #[cfg(feature = "gzip")]
+    Gzip,
+    #[cfg(feature = "deflate")]
+    Deflate,
+    #[cfg(feature = "zstd")]
+    Zstd,
 }
 
 #[derive(Debug, Clone)]
 pub enum DataType {
-    MultiPart,
+    /// Key-value pairs sent as `application/x-www-form-urlencoded`, see `Multipart` for `multipart/form-data` bodies
+    FormUrlEncoded,
+    /// Text sent in the charset of the body
     PlainText,
+    /// Raw bytes sent exactly as they are
+    Bytes,
 }
 
 /// store request body content through a variety of methods, resulting in similar, or identical outputs via HTTP POST
@@ -61,15 +100,19 @@ pub struct PostData {
     pub method: DataType,
     /// The storage of raw text input (see PostData::from_str for more info)
     pub raw: String,
-    /// The storage for key-value pairs such as HashMap and Tuple inputs
-    pub kv_store: HashMap<String, String>,
+    /// The storage for key-value pairs such as HashMap and Tuple inputs, in the order they were added and keeping repeated keys
+    pub kv_store: Vec<(String, String)>,
+    /// The storage of raw byte input (see PostData::from_bytes for more info)
+    pub bytes: Vec<u8>,
+    /// The media type sent in the `Content-Type` header, without its charset
+    pub content_type: String,
+    /// The charset text and form bodies are encoded in, sent as the `charset` parameter of the `Content-Type` header
+    pub charset: Option<String>,
 }
 
 /// All the nitty gritty of a Response, put into a nicely formatted, easy to use structure for you to use
 #[derive(Debug, Clone)]
 pub struct Response {
-    /// The raw response body, minus the headers and status line
-    pub raw: String,
     /// The protocol __version__ (almost always HTTP/1.1) which was used in the response body
     pub protocol: Option<String>,
     /// The status number of the response (200, 301, 404, 500 etc...)
@@ -77,19 +120,39 @@ pub struct Response {
     /// The message provided by the server to go with this response
     pub status_text: Option<String>,
     //pub content_type: String,
-    /// A HashMap containing the values of all headers, linked to their keys
-    pub headers: HashMap<String, String>,
+    /// Every header the server sent, including each `Set-Cookie` line, in the order they were received
+    pub headers: HeaderMap,
     /// A running total of all the headers stored in the `headers` value
     pub header_count: usize,
-    /// A HashMap containing a list of all cookies set at the request of the server, linked to their keys
-    pub cookies: HashMap<String, Cookie>,
+    /// Every cookie set at the request of the server, in the order the `Set-Cookie` headers were received, see `cookie` to find one by name
+    pub cookies: Vec<Cookie>,
     /// A running total of all the cookies stored in the `cookies` value
     pub cookie_count: usize,
-    /// The (optional) body of the response, not all responses have these
-    pub body: Option<String>,
+    /// The (optional) body of the response exactly as the server sent it, not all responses have these.
+    /// See `bytes`, `text` and `text_with_charset` for convenient access
+    pub body: Option<Vec<u8>>,
+    /// The trailer fields sent after a chunked body
+    pub trailers: HeaderMap,
+    /// The `Content-Encoding` the server sent, if Curio decoded the body. The header itself is removed, as it no longer describes `body`
+    pub content_encoding: Option<String>,
+    /// The size of the body as it was sent, before it was decoded
+    pub compressed_size: Option<u64>,
     /// Any warnings about the contents of your request, for example if the server requested an upgrade to HTTPS there will be an automated warning arrive here.
     /// These can be ignored, but it might be worth fixing them before releasing your project to increase performance a tiny fraction
     pub warnings: Vec<String>,
+    /// Every redirect which was followed to reach this response, in order
+    pub redirects: Vec<Redirect>,
+}
+
+/// A redirect which was followed on the way to a response
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    /// The status of the redirect response (301, 302, 303, 307 or 308)
+    pub status: isize,
+    /// The url which was redirected
+    pub from: String,
+    /// The url the redirect led to, with a relative `Location` already resolved
+    pub to: String,
 }
 
 /// The structure used for internal parsing of headers
@@ -108,45 +171,352 @@ pub struct Cookie {
     pub name: String,
     /// The cookie's value
     pub value: String,
-    /// When the cookie should expire
-    pub expires: Option<String>,
-    /// The maximum age of the cookie, in seconds
-    pub max_age: Option<usize>,
-    /// The path this cookie is applied to
+    /// When the cookie should expire, parsed from its `Expires` attribute
+    pub expires: Option<SystemTime>,
+    /// The maximum age of the cookie, in seconds. Zero or a negative age expires the cookie straight away, and takes precedence over `expires`
+    pub max_age: Option<i64>,
+    /// The path this cookie is applied to, `None` when the attribute is missing or does not start with `/`
     pub path: Option<String>,
-    /// The domain the cookie is applied to
+    /// The domain the cookie is applied to, in lowercase and without a leading dot
     pub domain: Option<String>,
-    /// Whether the cookie can be transferred between websites
-    pub same_site: Option<String>,
+    /// Whether the cookie can be transferred between websites, `None` when the attribute is missing or has an unknown value
+    pub same_site: Option<SameSite>,
     /// Whether this cookie is restricted from HTTPS requests
     pub http_only: bool,
     /// If the cookie requires HTTPS to be set
     pub secure: bool,
 }
 
+/// Limits on how long each part of a request may take, `None` means no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timeouts {
+    /// Resolving the host, connecting and completing the TLS handshake
+    pub connect: Option<Duration>,
+    /// Each read from the server
+    pub read: Option<Duration>,
+    /// Each write to the server
+    pub write: Option<Duration>,
+    /// The whole request, including the preflight request, redirects and downloading the body
+    pub total: Option<Duration>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ClientConfig {
     pub no_parse: bool,
     pub force_https: bool,
+    /// The most redirects followed for one request before it fails with `Error::TooManyRedirects`, `0` returns redirects as they are
     pub redirect_limit: u8,
+    /// Whether redirects from HTTP to HTTPS are followed, a warning is added to the response when they are
     pub auto_upgrade: bool,
     pub max_queue_length: usize,
+    /// Whether an OPTIONS request is sent first to check that the server allows the request method
     pub perform_preflight: bool,
+    /// The most connections open to each host at once, further requests to the host wait for one to be returned to the pool.
+    /// `0` disables connection reuse
     pub connection_limit: u8,
+    /// Whether every request opens a fresh connection instead of reusing one from the pool
     pub cycle_connections: bool,
+    /// How long a connection may wait in the pool for another request before it is closed
+    pub idle_timeout: Duration,
+    /// The largest response body accepted, in bytes. Larger bodies fail with `Error::BodyTooLarge`
+    pub max_body_size: Option<u64>,
+    /// Whether `Client::send` turns `4xx` and `5xx` responses into errors (see `Response::error_for_status`)
+    pub error_for_status: bool,
+    /// Whether compressed bodies are decoded, and the codings Curio can decode advertised with `Accept-Encoding`.
+    /// Decoding is only available when Curio is built with the `gzip`, `deflate` or `brotli` features
+    pub decompress: bool,
+    /// The default timeouts for every request, a request can override each of them
+    pub timeouts: Timeouts,
+    /// Whether `Client` stores the cookies set by responses in its `CookieJar` and sends them with later requests
+    pub store_cookies: bool,
+}
+
+impl Default for ClientConfig {
+    fn default() -> ClientConfig {
+        ClientConfig {
+            no_parse: false,
+            force_https: false,
+            redirect_limit: 10,
+            auto_upgrade: true,
+            max_queue_length: 10,
+            perform_preflight: true,
+            connection_limit: 5,
+            cycle_connections: false,
+            idle_timeout: Duration::from_secs(90),
+            max_body_size: None,
+            error_for_status: false,
+            decompress: true,
+            timeouts: Timeouts::default(),
+            store_cookies: true,
+        }
+    }
+}
+
+impl Timeouts {
+    /// Fills in the limits which are not set with the ones from `defaults`
+    pub fn or(&self, defaults: &Timeouts) -> Timeouts {
+        Timeouts {
+            connect: self.connect.or(defaults.connect),
+            read: self.read.or(defaults.read),
+            write: self.write.or(defaults.write),
+            total: self.total.or(defaults.total),
+        }
+    }
+}
+
+impl Compression {
+    /// The name of the coding, as it is sent in the `Content-Encoding` header
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => "gzip",
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => "deflate",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => "zstd",
+        }
+    }
+}
+
+impl RequestType {
+    /// Creates a `RequestType` from a method token.
+    /// The standard methods map onto their own variants, anything else becomes `RequestType::Custom`.
+    /// Methods are case-sensitive, so `"propfind"` is not the same method as `"PROPFIND"`.
+    /// ```
+    /// # use curio::structs::RequestType;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let method = RequestType::from_method("PROPFIND")?;
+    ///
+    ///     assert_eq!(method.as_str(), "PROPFIND");
+    ///     assert!(method.is_safe());
+    ///     assert!(RequestType::from_method("BAD METHOD").is_err());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_method<S: Into<String>>(method: S) -> Result<RequestType> {
+        let method = method.into();
+        let request_type = match method.as_str() {
+            "GET" => RequestType::Get,
+            "POST" => RequestType::Post,
+            "PUT" => RequestType::Put,
+            "HEAD" => RequestType::Head,
+            "DELETE" => RequestType::Delete,
+            "PATCH" => RequestType::Patch,
+            "OPTIONS" => RequestType::Options,
+            _ => RequestType::Custom(method),
+        };
+        request_type.validate()?;
+        Ok(request_type)
+    }
+
+    /// The method token as it is written on the request line
+    pub fn as_str(&self) -> &str {
+        match self {
+            RequestType::Get => "GET",
+            RequestType::Post => "POST",
+            RequestType::Put => "PUT",
+            RequestType::Head => "HEAD",
+            RequestType::Delete => "DELETE",
+            RequestType::Patch => "PATCH",
+            RequestType::Options => "OPTIONS",
+            RequestType::Custom(method) => method.as_str(),
+        }
+    }
+
+    /// Whether the method is safe (read-only) according to the IANA HTTP method registry.
+    /// Unregistered methods are never considered safe.
+    pub fn is_safe(&self) -> bool {
+        matches!(self.as_str(), "GET" | "HEAD" | "OPTIONS" | "TRACE" | "PROPFIND" | "REPORT" | "SEARCH")
+    }
+
+    /// Whether repeating the method has the same effect as sending it once, according to the IANA HTTP method registry.
+    /// Unregistered methods are never considered idempotent, so they are never retried or replayed automatically.
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self.as_str(),
+            "PUT" | "DELETE" | "ACL" | "BASELINE-CONTROL" | "BIND" | "CHECKIN" | "CHECKOUT" | "COPY" | "LABEL"
+            | "LINK" | "MERGE" | "MKACTIVITY" | "MKCALENDAR" | "MKCOL" | "MKREDIRECTREF" | "MKWORKSPACE" | "MOVE"
+            | "ORDERPATCH" | "PROPPATCH" | "REBIND" | "UNBIND" | "UNCHECKOUT" | "UNLINK" | "UNLOCK" | "UPDATE"
+            | "UPDATEREDIRECTREF" | "VERSION-CONTROL")
+    }
+
+    /// Whether a request with this method announces a `Content-Length` even when no body has been set.
+    /// Other methods only carry the body headers when a body is present.
+    pub fn expects_body(&self) -> bool {
+        matches!(self, RequestType::Post | RequestType::Put | RequestType::Patch)
+    }
+
+    /// Whether the method is allowed to carry a request body at all
+    pub fn allows_body(&self) -> bool {
+        !matches!(self.as_str(), "TRACE")
+    }
+
+    /// Checks that the method is a valid HTTP token, and that it is not `CONNECT`, which Curio does not support
+    pub fn validate(&self) -> Result<()> {
+        let method = self.as_str();
+        if !parsers::is_token(method) || method == "CONNECT" {
+            return Err(Error::InvalidMethod(method.to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for RequestType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[doc(hidden)]
 impl Response {
     #[doc(hidden)]
-    pub fn new(body: String, head: Vec<String>) -> Response {
-        utils::new_response(body, head)
+    pub fn new<B: Into<Vec<u8>>>(body: B, head: Vec<String>) -> Result<Response> {
+        utils::new_response(body.into(), head)
+    }
+}
+
+impl Response {
+    /// The body exactly as the server sent it, empty if the response has no body.
+    /// Use this for images, archives and any other binary content:
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let response = Request::get("https://example.com/logo.png").send()?;
+    ///
+    ///     std::fs::write("logo.png", response.bytes())?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn bytes(&self) -> &[u8] {
+        self.body.as_deref().unwrap_or_default()
+    }
+
+    /// The body as UTF-8 text, with any invalid sequences replaced by U+FFFD
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(self.bytes()).into_owned()
+    }
+
+    /// The body decoded from the `charset` given in the `Content-Type` header, or from `default_charset` if there is none.
+    /// Fails with `Error::UnsupportedCharset` for character sets other than UTF-8, US-ASCII, ISO-8859-1, windows-1252 and UTF-16:
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let head = vec![
+    ///         String::from("HTTP/1.1 200 OK"),
+    ///         String::from("Content-Type: text/plain; charset=ISO-8859-1"),
+    ///     ];
+    ///     let response = Response::new(vec![0x63, 0x61, 0x66, 0xE9], head)?;
+    ///
+    ///     assert_eq!(response.text_with_charset("utf-8")?, "café");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn text_with_charset(&self, default_charset: &str) -> Result<String> {
+        let charset = self.charset().unwrap_or_else(|| default_charset.to_string());
+        utils::charset::decode(self.bytes(), &charset)
+    }
+
+    /// The `charset` parameter of the `Content-Type` header, in lowercase
+    pub fn charset(&self) -> Option<String> {
+        self.headers.get("Content-Type").and_then(utils::charset::charset_of)
+    }
+
+    /// Whether the response has a `2xx` status
+    pub fn is_success(&self) -> bool {
+        matches!(self.status, Some(200..=299))
+    }
+
+    /// Turns a `4xx` or `5xx` response into the matching error, such as `Error::E404NotFound`, and passes any other response through.
+    /// The error keeps the response, so its body can still be read after using `?`:
+    /// ```
+    /// # use curio::prelude::*;
+    /// # use curio::transport::MockTransport;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mock = MockTransport::new();
+    ///     mock.push_response("HTTP/1.1 204 No Content\r\n\r\nHTTP/1.1 404 Not Found\r\nContent-Length: 7\r\n\r\nmissing");
+    ///
+    ///     let error = Request::get("https://example.com/missing")
+    ///         .send_with(&mock)?
+    ///         .error_for_status()
+    ///         .unwrap_err();
+    ///
+    ///     assert_eq!(error.status(), Some(404));
+    ///     assert_eq!(error.response().unwrap().text(), "missing");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn error_for_status(self) -> Result<Response> {
+        match self.status {
+            Some(400..=599) => Err(crate::types::err_from_code(self)),
+            _ => Ok(self),
+        }
+    }
+
+    /// The body parsed as an `application/x-www-form-urlencoded` form, as OAuth token endpoints and many older APIs reply with.
+    /// The pairs keep their order and any repeated keys:
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let head = vec![
+    ///         String::from("HTTP/1.1 200 OK"),
+    ///         String::from("Content-Type: application/x-www-form-urlencoded"),
+    ///     ];
+    ///     let response = Response::new("access_token=a%2Bb&scope=read+write", head)?;
+    ///
+    ///     let form = response.form();
+    ///     assert_eq!(form.get("access_token").unwrap(), "a+b");
+    ///     assert_eq!(form.get("scope").unwrap(), "read write");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn form(&self) -> PostData {
+        PostData::from_form_urlencoded(self.bytes())
+    }
+
+    /// The body parsed as JSON into any type implementing `serde::Deserialize`, available with the `json` feature.
+    /// A body which does not match fails with `Error::Json`, which holds the status of the response and the start of its body:
+    /// ```
+    /// # use curio::prelude::*;
+    /// # #[cfg(feature = "json")]
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let head = vec![
+    ///         String::from("HTTP/1.1 200 OK"),
+    ///         String::from("Content-Type: application/json"),
+    ///     ];
+    ///     let response = Response::new(r#"{"id": 1, "tags": ["http", "rust"]}"#, head)?;
+    ///
+    ///     let value: std::collections::HashMap<String, serde_json::Value> = response.json()?;
+    ///     assert_eq!(value["id"], 1);
+    ///     Ok(())
+    /// }
+    /// # #[cfg(not(feature = "json"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        let body = self.bytes();
+        let json = body.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(body);
+
+        serde_json::from_slice(json).map_err(|source| Error::Json {
+            status: self.status,
+            excerpt: excerpt(body),
+            source,
+        })
+    }
+
+    /// The first cookie the response set with this name
+    pub fn cookie(&self, name: &str) -> Option<&Cookie> {
+        self.cookies.iter().find(|cookie| cookie.name == name)
+    }
+
+    /// Reads the header `H`, such as `curio::headers::ETag`, returning `Ok(None)` when the response does not have it and `Error::InvalidHeader` when it can not be parsed
+    pub fn typed_header<H: TypedHeader>(&self) -> Result<Option<H>> {
+        self.headers.typed()
     }
 }
 
 impl Request {
     /// This method is used to GET content from a url:
-    /// ```
+    /// ```no_run
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///     let response = Request::get("https://example.com//path/to/resource")
@@ -157,26 +527,13 @@ impl Request {
     /// }
     /// ```
     pub fn get<A: Into<String>>(url: A) -> Request {
-        let url_string = url.into();
-        let (protocol, domain, port, path) = parsers::parse_url(&url_string);
-
-        Request {
-            request_type: RequestType::Get,
-            url_string,
-            domain,
-            port,
-            path,
-            protocol,
-            body: None,
-            headers: HashMap::<String, String>::new(),
-            header_count: 0,
-        }
+        Request::new(RequestType::Get, &url.into())
     }
 
 
     /// This method is used to read the HEAD content from a url.
     /// It is often used for checking the content-length before sending a GET request, but in our case it is open to you to use:
-    /// ```
+    /// ```no_run
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///     let response = Request::head("https://example.com//path/to/resource")
@@ -187,25 +544,12 @@ impl Request {
     /// }
     /// ```
     pub fn head<A: Into<String>>(url: A) -> Request {
-        let url_string = url.into();
-        let (protocol, domain, port, path) = parsers::parse_url(&url_string);
-
-        Request {
-            request_type: RequestType::Head,
-            url_string,
-            domain,
-            port,
-            path,
-            protocol,
-            body: None,
-            headers: HashMap::<String, String>::new(),
-            header_count: 0,
-        }
+        Request::new(RequestType::Head, &url.into())
     }
 
     /// This method is used to DELETE content from a url.
     /// It is used to inform the server that the content at the requested path is to be removed
-    /// ```
+    /// ```no_run
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///     let response = Request::delete("https://example.com//path/to/resource")
@@ -216,25 +560,12 @@ impl Request {
     /// }
     /// ```
     pub fn delete<A: Into<String>>(url: A) -> Request {
-        let url_string = url.into();
-        let (protocol, domain, port, path) = parsers::parse_url(&url_string);
-
-        Request {
-            request_type: RequestType::Delete,
-            url_string,
-            domain,
-            port,
-            path,
-            protocol,
-            body: None,
-            headers: HashMap::<String, String>::new(),
-            header_count: 0,
-        }
+        Request::new(RequestType::Delete, &url.into())
     }
 
     /// This method is used to check what can be done at the URL provided
     /// It is method is primarily used internally for confirming CORS protocol before allowing a request to send
-    /// ```
+    /// ```no_run
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///     let response = Request::options("https://example.com//path/to/resource")
@@ -245,25 +576,12 @@ impl Request {
     /// }
     /// ```
     pub fn options<A: Into<String>>(url: A) -> Request {
-        let url_string = url.into();
-        let (protocol, domain, port, path) = parsers::parse_url(&url_string);
-
-        Request {
-            request_type: RequestType::Options,
-            url_string,
-            domain,
-            port,
-            path,
-            protocol,
-            body: None,
-            headers: HashMap::<String, String>::new(),
-            header_count: 0,
-        }
+        Request::new(RequestType::Options, &url.into())
     }
 
     /// This method is used for transmitting data to the server through the request body.
     /// this example outputs the data as `application/x-www-form-urlencoded` and uses a tuple for key-value input:
-    /// ```
+    /// ```no_run
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///     let post_body: Vec<(&str, &str)> = vec!(
@@ -281,36 +599,219 @@ impl Request {
     /// }
     /// ```
     pub fn post<A: Into<String>>(url: A) -> Request {
-        let url_string = url.into();
-        let (protocol, domain, port, path) = parsers::parse_url(&url_string);
+        Request::new(RequestType::Post, &url.into())
+    }
+
+
+    /// This method is used for replacing the content at a url with the request body.
+    /// The body is set with `set_body` in the same way as a POST request:
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let post_data = PostData::from_str("This document replaces the old one");
+    ///     let response = Request::put("https://example.com//documents/1")
+    ///         .set_body(&post_data)
+    ///         .send()?;
+    ///
+    ///     println!("{:#?}", response);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn put<A: Into<String>>(url: A) -> Request {
+        Request::new(RequestType::Put, &url.into())
+    }
+
+    /// This method is used for applying a partial modification to the content at a url.
+    /// The body is set with `set_body` in the same way as a POST request:
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let post_body: Vec<(&str, &str)> = vec!(
+    ///         ("author", "Altrius")
+    ///     );
+    ///
+    ///     let post_data = PostData::from_tuple(post_body);
+    ///     let response = Request::patch("https://example.com//documents/1")
+    ///         .set_body(&post_data)
+    ///         .send()?;
+    ///
+    ///     println!("{:#?}", response);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn patch<A: Into<String>>(url: A) -> Request {
+        Request::new(RequestType::Patch, &url.into())
+    }
+
+    /// This method is used to send any other method to a url, such as the WebDAV `PROPFIND`, `MKCOL` or `LOCK` methods.
+    /// A body can be set with `set_body` if the method takes one, and the method token is validated when the request is sent:
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let response = Request::custom("PROPFIND", "https://example.com//calendars/altrius/")
+    ///         .set_header("Depth", "1")
+    ///         .send()?;
+    ///
+    ///     println!("{:#?}", response);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn custom<M: Into<String>, A: Into<String>>(method: M, url: A) -> Request {
+        let method = method.into();
+        Request::new(RequestType::from_method(method.clone()).unwrap_or(RequestType::Custom(method)), &url.into())
+    }
+
+    // the request every constructor starts from, without a body or headers
+    fn new(request_type: RequestType, url: &str) -> Request {
+        let (protocol, domain, port, path) = parsers::parse_url(url);
 
         Request {
-            request_type: RequestType::Post,
-            url_string,
+            request_type,
+            url_string: url.to_string(),
             domain,
             port,
             path,
             protocol,
             body: None,
-            headers: HashMap::<String, String>::new(),
+            headers: HeaderMap::new(),
             header_count: 0,
+            timeouts: Timeouts::default(),
+            compression: None,
+            retry_uncompressed: false,
+            body_stream: None,
+            site_for_cookies: None,
+            unsupported_charset: None,
         }
     }
 
-
     /// This method is used to set the body of a request.
     /// It takes one parameter only, and that is a `PostData` structure.
-    /// This result of this method is only used in a POST request, it is not necessary for any other request type.
-    /// see the example of a POST request for usage
+    /// This result of this method is used by POST, PUT and PATCH requests, it is not necessary for any other request type.
+    /// see the example of a POST request for usage.
+    /// A body in a charset Curio can not encode makes the request fail with `Error::UnsupportedCharset` when it is sent
     pub fn set_body(&mut self, body: &PostData) -> &mut Request {
-        self.body = Some(body.deserialize());
+        match body.deserialize() {
+            Ok(body) => {
+                self.body = Some(body);
+                self.unsupported_charset = None;
+            }
+            Err(_) => {
+                self.body = None;
+                self.unsupported_charset = body.charset.clone();
+            }
+        }
+        self.body_stream = None;
         self
     }
 
-    #[doc(hidden)]
-    /// This method is used to set a header on the resulting request method.
-    /// this example outputs the data as `application/x-www-form-urlencoded` and uses a tuple for key-value input:
+    /// Sets the body to `value` serialized as JSON, with a `Content-Type: application/json` header. Available with the `json` feature.
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// # #[cfg(feature = "json")]
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut post = std::collections::HashMap::new();
+    ///     post.insert("title", "Curio");
+    ///
+    ///     let response = Request::post("https://example.com/posts")
+    ///         .set_json(&post)?
+    ///         .send()?;
+    ///
+    ///     let created: std::collections::HashMap<String, serde_json::Value> = response.json()?;
+    ///     println!("{:#?}", created);
+    ///     Ok(())
+    /// }
+    /// # #[cfg(not(feature = "json"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn set_json<T: serde::Serialize + ?Sized>(&mut self, value: &T) -> Result<&mut Request> {
+        let body = PostData::json(value)?;
+        Ok(self.set_body(&body))
+    }
+
+    /// Sets a body which is read from `reader` while the request is sent, so large uploads never have to be held in memory.
+    /// When `length` is known it is sent as the `Content-Length`, otherwise the body is sent with chunked transfer encoding.
+    /// A streamed body can only be sent once, so requests which have to send it again, such as after a `307` redirect, fail with `Error::BodyNotRewindable`:
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let file = std::fs::File::open("telemetry.json")?;
+    ///     let length = file.metadata()?.len();
+    ///
+    ///     let response = Request::post("https://example.com/telemetry")
+    ///         .set_body_reader(file, "application/json", Some(length))
+    ///         .send()?;
+    ///
+    ///     println!("{:#?}", response);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_body_reader<R: Read + Send + 'static, C: Into<String>>(&mut self, reader: R, content_type: C, length: Option<u64>) -> &mut Request {
+        self.body_stream = Some(BodyStream::new(reader, content_type, length));
+        self.body = None;
+        self.unsupported_charset = None;
+        self
+    }
+
+    /// Sets a `multipart/form-data` body, which is streamed while the request is sent so file parts are never loaded into memory.
+    /// The body is sent with a `Content-Length` when the length of every part is known, and with chunked transfer encoding otherwise.
+    /// Like any streamed body it can only be sent once:
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut form = Multipart::new();
+    ///     form.add_text("description", "Quarterly report")
+    ///         .add_file("report", "report.pdf")?;
+    ///
+    ///     let response = Request::post("https://example.com/upload")
+    ///         .set_multipart(form)
+    ///         .send()?;
+    ///
+    ///     println!("{:#?}", response);
+    ///     Ok(())
+    /// }
     /// ```
+    pub fn set_multipart(&mut self, form: Multipart) -> &mut Request {
+        let content_type = form.content_type();
+        let length = form.length();
+        self.set_body_reader(form.into_reader(), content_type, length)
+    }
+
+    /// Compresses the body with `compression` and labels it with a `Content-Encoding` header.
+    /// Streamed bodies are compressed as they are sent, with chunked transfer encoding as their compressed length is not known in advance:
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// # use curio::structs::Compression;
+    /// # #[cfg(feature = "gzip")]
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let response = Request::post("https://example.com/telemetry")
+    ///         .set_body(&PostData::from_str("{\"events\": []}"))
+    ///         .set_compression(Compression::Gzip)
+    ///         .set_retry_uncompressed(true)
+    ///         .send()?;
+    ///
+    ///     println!("{:#?}", response);
+    ///     Ok(())
+    /// }
+    /// # #[cfg(not(feature = "gzip"))]
+    /// # fn main() {}
+    /// ```
+    pub fn set_compression(&mut self, compression: Compression) -> &mut Request {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Sets whether a compressed body held in memory is sent again uncompressed when the server answers `415 Unsupported Media Type`
+    pub fn set_retry_uncompressed(&mut self, retry: bool) -> &mut Request {
+        self.retry_uncompressed = retry;
+        self
+    }
+
+    /// This method is used to set a header on the resulting request method, replacing any value it already has (names are compared ignoring case).
+    /// Headers set here are sent alongside Curio's defaults, in the order they were set, and replace any default with the same name (e.g. `User-Agent`).
+    /// `Content-Length` and `Transfer-Encoding` are always generated from the body, so setting them has no effect.
+    /// Names which are not valid tokens and values containing line breaks make `send` fail with `Error::InvalidHeader`:
+    /// ```no_run
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///     let response = Request::get("https://example.com//documents")
@@ -322,48 +823,347 @@ impl Request {
     /// }
     /// ```
     pub fn set_header<A: Into<String>>(&mut self, key: A, value: A) -> &mut Request {
-        self.headers.insert(key.into(), value.into());
-        self.header_count += 1;
+        self.headers.insert_unchecked(key, value);
+        self.header_count = self.headers.len();
+        self
+    }
+
+    /// Sets a header from its type, such as `curio::headers::ContentType`, replacing the values it had
+    pub fn set_typed_header<H: TypedHeader>(&mut self, header: &H) -> &mut Request {
+        self.set_header(String::from(H::NAME), header.format())
+    }
+
+    /// Adds a value to a header, keeping the values it already has, for headers which may be repeated such as `Accept` or `Forwarded`
+    pub fn append_header<A: Into<String>>(&mut self, key: A, value: A) -> &mut Request {
+        self.headers.append_unchecked(key, value);
+        self.header_count = self.headers.len();
+        self
+    }
+
+    /// Sets the site the request is made on behalf of, such as the page a link was followed from.
+    /// When it is a different site, cookies with `SameSite=Strict` are not sent, and `SameSite=Lax` cookies are only sent with safe methods such as GET:
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// # use curio::structs::Client;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut client = Client::new();
+    ///     let response = client.send(Request::get("https://example.com/account")
+    ///         .set_site_for_cookies("https://other.example.org/"))?;
+    ///
+    ///     println!("{:#?}", response);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_site_for_cookies<S: Into<String>>(&mut self, url: S) -> &mut Request {
+        self.site_for_cookies = Some(url.into());
+        self
+    }
+
+    /// Limits how long the whole request may take, including the preflight request, redirects and downloading the body.
+    /// When it runs out the request fails with `Error::Timeout` in the `TimeoutPhase::Total` phase:
+    /// ```no_run
+    /// # use curio::prelude::*;
+    /// # use std::time::Duration;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let response = Request::get("https://example.com/slow")
+    ///         .set_timeout(Duration::from_secs(10))
+    ///         .set_connect_timeout(Duration::from_secs(2))
+    ///         .send()?;
+    ///
+    ///     println!("{:#?}", response);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Request {
+        self.timeouts.total = Some(timeout);
+        self
+    }
+
+    /// Limits how long resolving the host, connecting and the TLS handshake may take
+    pub fn set_connect_timeout(&mut self, timeout: Duration) -> &mut Request {
+        self.timeouts.connect = Some(timeout);
+        self
+    }
+
+    /// Limits how long each read from the server may wait
+    pub fn set_read_timeout(&mut self, timeout: Duration) -> &mut Request {
+        self.timeouts.read = Some(timeout);
+        self
+    }
+
+    /// Limits how long each write to the server may wait
+    pub fn set_write_timeout(&mut self, timeout: Duration) -> &mut Request {
+        self.timeouts.write = Some(timeout);
         self
     }
 
     /// The `send` method is used to deserialize and send the resulting request to the destination, it uses a series of checks to confirm that it is doing what you want it to do
     /// see any of the above examples for information on how to use this method.
-    pub fn send(&self/*,conn: &mut Connection //This is for the alpha branch*/) -> Result<Response, Box<dyn std::error::Error>> {
-        return match self.protocol {
-            HTTPProtocol::HTTPS => {
-                match self.request_type {
-                    RequestType::Get => crate::tls::get(&self.domain, &self.path, false),
-                    RequestType::Head => crate::tls::head(&self.domain, &self.path, false),
-                    RequestType::Options => crate::tls::options(&self.domain, &self.path, false),
-                    RequestType::Delete => crate::tls::delete(&self.domain, &self.path, false),
-                    RequestType::Post => crate::tls::post(&self.domain, &self.path, self.clone(), false),
-                    _ => {
-                        println!("Error: {:?} is currently not implemented, switching to GET", self.request_type);
-                        crate::tcp::get(&self.domain, &self.path)
-                    }
+    /// Anything that goes wrong, from DNS failures to malformed responses, is returned as a `curio::types::Error` rather than panicking.
+    pub fn send(&self) -> Result<Response> {
+        self.send_with(&NetTransport)
+    }
+
+    /// Sends the request through `transport` instead of opening a network connection directly.
+    /// This is how a `MockTransport` can stand in for a real server:
+    /// ```
+    /// # use curio::prelude::*;
+    /// # use curio::transport::MockTransport;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mock = MockTransport::new();
+    ///     // the preflight and the request itself share one keep-alive connection
+    ///     mock.push_response("HTTP/1.1 204 No Content\r\n\r\nHTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n");
+    ///
+    ///     let response = Request::put("http://example.com/documents/1")
+    ///         .set_body(&PostData::from_str("content"))
+    ///         .send_with(&mock)?;
+    ///
+    ///     assert_eq!(response.status, Some(201));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn send_with(&self, transport: &dyn Transport) -> Result<Response> {
+        self.execute(&Pool::new(), transport, &ClientConfig::default(), None)
+    }
+
+    /// Sends the request and returns as soon as the response head has been read, so the body can be processed while it downloads.
+    /// The body is read through `StreamingResponse::body`, see the `curio::body` module for an example.
+    pub fn send_streaming(&self) -> Result<StreamingResponse> {
+        self.execute_streaming(&Pool::new(), &NetTransport, &ClientConfig::default(), None)
+    }
+
+    pub(crate) fn execute(&self, pool: &Pool, transport: &dyn Transport, config: &ClientConfig, jar: Option<&CookieJar>) -> Result<Response> {
+        self.execute_streaming(pool, transport, config, jar)?.into_response()
+    }
+
+    /// Sends the request, following redirects. When a `jar` is given, its cookies are sent with each hop and the cookies each response sets are stored in it
+    pub(crate) fn execute_streaming(&self, pool: &Pool, transport: &dyn Transport, config: &ClientConfig, jar: Option<&CookieJar>) -> Result<StreamingResponse> {
+        let deadline = self.timeouts.or(&config.timeouts).total.map(|total| Instant::now() + total);
+
+        self.request_type.validate()?;
+        parsers::try_parse_url(&self.url_string)?;
+        parsers::check_path(&self.url_string, &self.path)?;
+        self.headers.validate()?;
+        if let Some(charset) = &self.unsupported_charset {
+            return Err(Error::UnsupportedCharset(charset.clone()));
+        }
+        if (self.body.is_some() || self.body_stream.is_some()) && !self.request_type.allows_body() {
+            return Err(Error::InvalidMethod(self.request_type.to_string()));
+        }
+
+        if config.perform_preflight && !matches!(self.request_type, RequestType::Options) {
+            let preflight = self.preflight_request().dispatch(pool, transport, config, deadline)?.into_response()?;
+            utils::check_preflight(&preflight, self.request_type.as_str())?;
+        }
+
+        let mut request = self.clone();
+        if config.decompress && !request.headers.contains("Accept-Encoding") {
+            if let Some(accept) = codec::encoding::accept_encoding() {
+                request.set_header(String::from("Accept-Encoding"), accept);
+            }
+        }
+
+        let mut redirects = Vec::new();
+        let mut warnings = Vec::new();
+
+        loop {
+            let mut streaming = match jar {
+                Some(jar) => request.dispatch_with_cookies(jar, pool, transport, config, deadline)?,
+                None => request.dispatch(pool, transport, config, deadline)?,
+            };
+
+            if streaming.response.status == Some(415) && request.compression.is_some() && request.retry_uncompressed && request.body_stream.is_none() {
+                streaming.into_response()?;
+                request.compression = None;
+                warnings.push(String::from("The server refused the compressed body, so this request was sent again uncompressed."));
+                continue;
+            }
+
+            let next = match request.redirect_for(&streaming.response)? {
+                Some(next) if config.redirect_limit > 0 => next,
+                _ => {
+                    streaming.response.redirects = redirects;
+                    streaming.response.warnings.append(&mut warnings);
+                    return Ok(streaming);
+                }
+            };
+
+            if request.protocol == HTTPProtocol::HTTP && next.protocol == HTTPProtocol::HTTPS {
+                if !config.auto_upgrade {
+                    streaming.response.redirects = redirects;
+                    streaming.response.warnings.append(&mut warnings);
+                    return Ok(streaming);
                 }
+                warnings.push(String::from("This request was automatically upgraded to HTTPS at the request of the server."));
             }
-            HTTPProtocol::HTTP => {
-                match self.request_type {
-                    RequestType::Get => crate::tcp::get(&self.domain, &self.path),
-                    RequestType::Head => crate::tcp::head(&self.domain, &self.path),
-                    RequestType::Options => crate::tcp::options(&self.domain, &self.path),
-                    RequestType::Delete => crate::tcp::delete(&self.domain, &self.path),
-                    RequestType::Post => crate::tcp::post(&self.domain, &self.path, self.clone()),
-                    _ => {
-                        println!("Error: {:?} is currently not implemented, switching to GET", self.request_type);
-                        crate::tcp::get(&self.domain, &self.path)
+
+            if redirects.len() >= config.redirect_limit as usize {
+                return Err(Error::TooManyRedirects { limit: config.redirect_limit });
+            }
+
+            // the body of the redirect is read so that its connection can be reused for the next hop
+            let response = streaming.into_response()?;
+            redirects.push(Redirect {
+                status: response.status.unwrap_or(0),
+                from: request.url_string.clone(),
+                to: next.url_string.clone(),
+            });
+            request = next;
+        }
+    }
+
+    /// Builds the request which follows `response`, if it is a redirect with a `Location` header naming an HTTP or HTTPS url.
+    /// A 303 turns the request into a GET, as do a 301 or 302 in reply to a POST, while 307 and 308 keep the method and body.
+    /// Credentials are not forwarded when the redirect leads to a different origin.
+    fn redirect_for(&self, response: &Response) -> Result<Option<Request>> {
+        let status = response.status.unwrap_or(0);
+        if !matches!(status, 301 | 302 | 303 | 307 | 308) {
+            return Ok(None);
+        }
+
+        let location = match response.headers.get("Location") {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+
+        let url_string = parsers::resolve_url(&self.url_string, location)?;
+        // other schemes, such as the `myapp://callback` of an OAuth flow, are left for the caller to handle
+        let scheme = url_string.split(':').next().unwrap_or_default();
+        if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+            return Ok(None);
+        }
+        let (protocol, domain, port, path) = parsers::try_parse_url(&url_string)?;
+
+        let mut next = self.clone();
+        next.url_string = url_string;
+        next.protocol = protocol;
+        next.domain = domain;
+        next.port = port;
+        next.path = path;
+
+        let becomes_get = match status {
+            303 => !matches!(self.request_type, RequestType::Get | RequestType::Head),
+            301 | 302 => matches!(self.request_type, RequestType::Post),
+            _ => false,
+        };
+        if becomes_get {
+            next.request_type = RequestType::Get;
+            next.body = None;
+            next.body_stream = None;
+            next.compression = None;
+            for name in ["Content-Type", "Content-Length", "Content-Encoding", "Transfer-Encoding"].iter() {
+                next.headers.remove(name);
+            }
+        }
+
+        if (&self.protocol, self.domain.to_ascii_lowercase(), self.port) != (&next.protocol, next.domain.to_ascii_lowercase(), next.port) {
+            for name in ["Authorization", "Proxy-Authorization", "Cookie"].iter() {
+                next.headers.remove(name);
+            }
+        }
+        next.header_count = next.headers.len();
+
+        Ok(Some(next))
+    }
+
+    /// Dispatches the request with the cookies from `jar` added to its own `Cookie` header, then stores the cookies set by the response.
+    /// The request is left with only its own cookies, as the jar picks them again for the next hop.
+    fn dispatch_with_cookies(&mut self, jar: &CookieJar, pool: &Pool, transport: &dyn Transport, config: &ClientConfig, deadline: Option<Instant>) -> Result<StreamingResponse> {
+        let result = match jar.header_for(self) {
+            Some(stored) => {
+                let own = self.headers.clone();
+                let cookie = own.get_all("Cookie").into_iter().map(String::from).chain(std::iter::once(stored)).collect::<Vec<String>>().join("; ");
+                self.headers.insert_unchecked("Cookie", cookie);
+
+                let result = self.dispatch(pool, transport, config, deadline);
+                self.headers = own;
+                result
+            }
+            None => self.dispatch(pool, transport, config, deadline),
+        };
+
+        let streaming = result?;
+        jar.store_response(self, &streaming.response);
+        Ok(streaming)
+    }
+
+    /// Sends the request over a connection from `pool`, opening one through `transport` if none is idle, and reads the response head.
+    /// The connection stays with the returned body reader until the body has been read.
+    /// If a reused connection turns out to have been closed by the server, the request is sent again on a new connection,
+    /// as long as it is idempotent or the connection failed before the request was written.
+    fn dispatch(&self, pool: &Pool, transport: &dyn Transport, config: &ClientConfig, deadline: Option<Instant>) -> Result<StreamingResponse> {
+        let (secure, default_port) = match self.protocol {
+            HTTPProtocol::HTTPS => (true, 443),
+            HTTPProtocol::HTTP => (false, 80),
+        };
+        let key = PoolKey::new(&self.domain, self.port, secure);
+        let timeouts = self.timeouts.or(&config.timeouts);
+
+        loop {
+            let mut connection = codec::limit(timeouts.connect, deadline)
+                .and_then(|timeout| pool.checkout(transport, &key, config, timeout))
+                .map_err(|e| codec::past_deadline(e, deadline))?;
+            let reused = connection.requests > 0;
+
+            let written = codec::limit(timeouts.write, deadline)
+                .and_then(|timeout| Ok(connection.stream.get_ref().set_write_timeout(timeout)?))
+                .and_then(|_| codec::write_request(connection.stream.get_mut(), self, default_port))
+                .map_err(|e| e.in_phase(TimeoutPhase::Write));
+            let write_failed = written.is_err();
+
+            let result = written.and_then(|_| {
+                let mut reader = TimedReader::new(&mut connection.stream, timeouts.read, deadline);
+                codec::read_final_head(&mut reader)
+            });
+
+            match result.map_err(|e| codec::past_deadline(e, deadline)) {
+                Ok(mut response) => {
+                    connection.requests += 1;
+                    let framing = match codec::framing(&response, &self.request_type, config.max_body_size) {
+                        Ok(framing) => framing,
+                        Err(e) => {
+                            pool.discard(connection);
+                            return Err(e);
+                        }
+                    };
+
+                    let reusable = codec::keeps_alive(&response, self);
+                    let mut body = BodyReader::new(connection, framing, pool, config, reusable, timeouts.read, deadline);
+
+                    if config.decompress && framing != Framing::Empty {
+                        if let Some(codings) = codec::encoding::prepare(&mut response) {
+                            body.decode(&codings)?;
+                        }
                     }
+                    return Ok(StreamingResponse { response, body });
+                }
+                Err(e) => {
+                    pool.discard(connection);
+                    if reused && utils::is_stale_connection(&e) && (write_failed || self.request_type.is_idempotent()) {
+                        continue;
+                    }
+                    return Err(e);
                 }
             }
-        };
+        }
+    }
+
+    /// The OPTIONS request used to check that the server allows this request before it is sent
+    fn preflight_request(&self) -> Request {
+        let mut preflight = self.clone();
+        preflight.request_type = RequestType::Options;
+        preflight.body = None;
+        preflight.body_stream = None;
+        preflight.compression = None;
+        preflight.headers = HeaderMap::new();
+        preflight.header_count = 0;
+        preflight
     }
 }
 
 
 impl PostData {
-    /// Returns the value stored at `key` or `None`.
+    /// Returns the first value stored at `key` or `None`.
     /// ```
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -379,10 +1179,28 @@ impl PostData {
     /// }
     /// ```
     pub fn get<Q: Into<String>>(&self, k: Q) -> Option<&String> {
-        return self.kv_store.get(k.into().as_str());
+        let k = k.into();
+        self.kv_store.iter().find(|(key, _)| *key == k).map(|(_, value)| value)
+    }
+
+    /// Returns every value stored at `key`, in the order they were added.
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let post_data = PostData::from_tuple(vec!(("tag", "rust"), ("tag", "http")));
+    ///
+    ///     assert_eq!(post_data.get_all("tag"), vec!["rust", "http"]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_all<Q: Into<String>>(&self, k: Q) -> Vec<&String> {
+        let k = k.into();
+        self.kv_store.iter().filter(|(key, _)| *key == k).map(|(_, value)| value).collect()
     }
 
-    /// Sets the value stored at `key` to `value`.
+    /// Sets the value stored at `key` to `value`, replacing every value already stored there.
+    /// The key keeps the position it had, or is added at the end if it is new.
+    /// Returns the number of pairs stored.
     /// ```
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -398,12 +1216,50 @@ impl PostData {
     /// }
     /// ```
     pub fn insert<K: Into<String>, V: Into<String>>(&mut self, k: K, v: V) -> usize {
-        self.kv_store.insert(k.into(), v.into());
-        return self.kv_store.len();
+        let (k, v) = (k.into(), v.into());
+
+        match self.kv_store.iter().position(|(key, _)| *key == k) {
+            Some(first) => {
+                self.kv_store[first].1 = v;
+                let mut position = 0;
+                self.kv_store.retain(|(key, _)| {
+                    let keep = position <= first || *key != k;
+                    position += 1;
+                    keep
+                });
+            }
+            None => self.kv_store.push((k, v)),
+        }
+        self.kv_store.len()
     }
 
+    /// Adds `value` at `key` after any values already stored there, so the key is sent more than once.
+    /// Returns the number of pairs stored.
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut post_data = PostData::from_tuple(vec!(("tag", "rust")));
+    ///     post_data.append("tag", "http");
+    ///
+    ///     assert_eq!(post_data.to_form_urlencoded(), "tag=rust&tag=http");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn append<K: Into<String>, V: Into<String>>(&mut self, k: K, v: V) -> usize {
+        self.kv_store.push((k.into(), v.into()));
+        self.kv_store.len()
+    }
 
-    /// Creates a new `PostData` instance from the provided string.
+    /// Removes every value stored at `key`, returning them in the order they were added.
+    pub fn remove<Q: Into<String>>(&mut self, k: Q) -> Vec<String> {
+        let k = k.into();
+        let (removed, kept) = self.kv_store.drain(..).partition::<Vec<(String, String)>, _>(|(key, _)| *key == k);
+        self.kv_store = kept;
+        removed.into_iter().map(|(_, value)| value).collect()
+    }
+
+    /// Creates a new `PostData` instance from the provided string, sent as `text/plain; charset=utf-8`.
+    /// Use `PostData::with_content_type` for text in other formats, such as JSON or XML.
     /// ```
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -413,15 +1269,86 @@ impl PostData {
     ///     Ok(())
     /// }
     /// ```
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str<S: Into<String>>(str: S) -> PostData {
+        PostData::text(str)
+    }
+
+    /// Creates a new `PostData` instance holding plain text, sent as `text/plain; charset=utf-8`.
+    pub fn text<S: Into<String>>(text: S) -> PostData {
         PostData {
             method: DataType::PlainText,
-            raw: str.into(),
-            kv_store: HashMap::<String, String>::new(),
+            raw: text.into(),
+            kv_store: Vec::new(),
+            bytes: Vec::new(),
+            content_type: String::from("text/plain"),
+            charset: Some(String::from("utf-8")),
         }
     }
 
+    /// Creates a new `PostData` instance holding text in the media type `content_type`, encoded as UTF-8 unless another charset is set.
+    /// A `charset` parameter in `content_type` is checked the same way as `PostData::set_charset`, failing with `Error::UnsupportedCharset`.
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let post_data = PostData::with_content_type("{\"title\": \"Curio\"}", "application/json")?;
+    ///
+    ///     assert_eq!(post_data.content_type_header(), "application/json; charset=utf-8");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_content_type<S: Into<String>, C: Into<String>>(text: S, content_type: C) -> Result<PostData> {
+        let mut post_data = PostData::text(text);
+        post_data.set_content_type(content_type)?;
+        Ok(post_data)
+    }
+
+    /// Creates a new `PostData` instance holding raw bytes in the media type `content_type`, which are sent exactly as they are.
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let post_data = PostData::from_bytes(vec![0x89, 0x50, 0x4E, 0x47], "image/png");
+    ///
+    ///     assert_eq!(post_data.content_type_header(), "image/png");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_bytes<B: Into<Vec<u8>>, C: Into<String>>(bytes: B, content_type: C) -> PostData {
+        let (content_type, charset) = split_charset(content_type.into());
+        PostData {
+            method: DataType::Bytes,
+            raw: "".to_string(),
+            kv_store: Vec::new(),
+            bytes: bytes.into(),
+            content_type,
+            charset,
+        }
+    }
+
+    /// Creates a new `PostData` instance holding `value` serialized as JSON, sent as `application/json`. Available with the `json` feature.
+    /// ```
+    /// # use curio::prelude::*;
+    /// # #[cfg(feature = "json")]
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut document = std::collections::BTreeMap::new();
+    ///     document.insert("title", "Curio");
+    ///
+    ///     let post_data = PostData::json(&document)?;
+    ///     assert_eq!(post_data.content_type_header(), "application/json");
+    ///     assert_eq!(post_data.bytes, br#"{"title":"Curio"}"#);
+    ///     Ok(())
+    /// }
+    /// # #[cfg(not(feature = "json"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::Serialize + ?Sized>(value: &T) -> Result<PostData> {
+        let bytes = serde_json::to_vec(value).map_err(|source| Error::Json { status: None, excerpt: String::new(), source })?;
+        Ok(PostData::from_bytes(bytes, "application/json"))
+    }
+
     /// Creates a new `PostData` instance from the provided key-value Vector of Tuple data.
+    /// The pairs are sent in the order given, including any repeated keys.
     /// ```
     /// # use curio::prelude::*;
     /// fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -436,20 +1363,14 @@ impl PostData {
     /// }
     /// ```
     pub fn from_tuple<S: Into<String>>(data: Vec<(S, S)>) -> PostData {
-        let mut kv_store = HashMap::<String, String>::new();
-
-        for (key, value) in data {
-            kv_store.insert(key.into(), value.into());
+        PostData {
+            kv_store: data.into_iter().map(|(key, value)| (key.into(), value.into())).collect(),
+            ..PostData::form()
         }
-
-        return PostData {
-            method: DataType::MultiPart,
-            raw: "".to_string(),
-            kv_store,
-        };
     }
 
     /// Creates a new `PostData` instance from the provided HashMap.
+    /// A HashMap has no order, so use `PostData::from_tuple` if the server expects the keys in a particular order.
     /// ```
     /// # use std::collections::HashMap;
     /// # use curio::prelude::*;
@@ -464,87 +1385,264 @@ impl PostData {
     /// }
     /// ```
     pub fn from_hash_map<S: Into<String>>(map: HashMap<S, S>) -> PostData {
-        let mut kv_store = HashMap::<String, String>::new();
-        for (key, value) in map {
-            kv_store.insert(key.into(), value.into());
+        PostData {
+            kv_store: map.into_iter().map(|(key, value)| (key.into(), value.into())).collect(),
+            ..PostData::form()
         }
+    }
 
-        return PostData {
-            method: DataType::MultiPart,
-            raw: "".to_string(),
-            kv_store,
-        };
+    /// Creates a new `PostData` instance by parsing an `application/x-www-form-urlencoded` string, such as a query string or a form body.
+    /// Repeated keys are kept in order, and `+` and percent-encoded bytes are decoded as UTF-8 (see also `Response::form`):
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let post_data = PostData::from_form_urlencoded("author=Altrius&timestamp=Fri%2C+28+Aug+2020");
+    ///
+    ///     assert_eq!(post_data.get("timestamp").unwrap(), "Fri, 28 Aug 2020");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_form_urlencoded<S: AsRef<[u8]>>(form: S) -> PostData {
+        PostData {
+            kv_store: utils::form::decode(form.as_ref()),
+            ..PostData::form()
+        }
+    }
+
+    /// Encodes the key-value pairs as an `application/x-www-form-urlencoded` string, the way browsers submit forms.
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let post_data = PostData::from_tuple(vec!(("timestamp", "Fri, 28 Aug 2020 10:55:44 +0000")));
+    ///
+    ///     assert_eq!(post_data.to_form_urlencoded(), "timestamp=Fri%2C+28+Aug+2020+10%3A55%3A44+%2B0000");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn to_form_urlencoded(&self) -> String {
+        utils::form::encode(&self.kv_store, self.charset.as_deref().unwrap_or("utf-8"))
+    }
+
+    /// Sets the media type sent in the `Content-Type` header, replacing the default of the constructor.
+    /// A `charset` parameter in `content_type` sets the charset as well, failing with `Error::UnsupportedCharset` the same way as `PostData::set_charset`.
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut post_data = PostData::text("<note>Curio</note>");
+    ///     post_data.set_content_type("application/xml")?;
+    ///
+    ///     assert_eq!(post_data.content_type_header(), "application/xml; charset=utf-8");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_content_type<C: Into<String>>(&mut self, content_type: C) -> Result<&mut PostData> {
+        let (content_type, charset) = split_charset(content_type.into());
+        if let Some(charset) = charset {
+            self.set_charset(charset)?;
+        }
+        self.content_type = content_type;
+        Ok(self)
+    }
+
+    /// Sets the charset text and form bodies are encoded in, and labels the `Content-Type` header with it.
+    /// UTF-8, US-ASCII, ISO-8859-1, windows-1252 and UTF-16 are supported, other charsets fail with `Error::UnsupportedCharset`.
+    /// Characters the charset can not represent are sent as `?` in text, and as `&#NNNN;` numeric character references in forms, which are sent as UTF-8 instead of UTF-16.
+    /// ```
+    /// # use curio::prelude::*;
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut post_data = PostData::text("café");
+    ///     post_data.set_charset("iso-8859-1")?;
+    ///
+    ///     assert_eq!(post_data.content_type_header(), "text/plain; charset=iso-8859-1");
+    ///     assert_eq!(post_data.deserialize()?.1, vec![0x63, 0x61, 0x66, 0xE9]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_charset<C: Into<String>>(&mut self, charset: C) -> Result<&mut PostData> {
+        let charset = charset.into();
+        utils::charset::encode("", &charset)?;
+        self.charset = Some(charset);
+        Ok(self)
+    }
+
+    /// The value of the `Content-Type` header sent with this body
+    pub fn content_type_header(&self) -> String {
+        match &self.charset {
+            // forms are never encoded in UTF-16, so the label names the charset they fall back to
+            Some(charset) if matches!(self.method, DataType::FormUrlEncoded) => format!("{}; charset={}", self.content_type, utils::form::output_charset(charset)),
+            Some(charset) => format!("{}; charset={}", self.content_type, charset),
+            None => self.content_type.clone(),
+        }
     }
 
+    /// The `Content-Type` header and the encoded body, failing with `Error::UnsupportedCharset` when text or a form is in a charset Curio can not encode,
+    /// which can only happen when one was assigned to the `charset` field directly
     #[doc(hidden)]
-    pub fn deserialize(&self) -> (String, String) {
-        let mut form = String::new();
-        let mut body_type = String::from("application/json");
-        if self.kv_store.len() == 0 {
-            form = self.raw.clone();
-        } else {
-            body_type = String::from("application/x-www-form-urlencoded");
-            let passes = 0;
-            for (key, value) in self.kv_store.clone() {
-                if passes == 0 {
-                    form = format!("{}={}", key, value)
-                } else {
-                    form = format!("{}&{}={}", form, key, value)
-                }
+    pub fn deserialize(&self) -> Result<(String, Vec<u8>)> {
+        let charset = self.charset.as_deref().unwrap_or("utf-8");
+        let content = match self.method {
+            DataType::PlainText => utils::charset::encode(&self.raw, charset)?,
+            DataType::FormUrlEncoded => {
+                // `to_form_urlencoded` falls back to UTF-8 rather than fail
+                utils::charset::encode("", utils::form::output_charset(charset))?;
+                self.to_form_urlencoded().into_bytes()
             }
+            DataType::Bytes => self.bytes.clone(),
+        };
+        Ok((self.content_type_header(), content))
+    }
+
+    // an empty form, which the key-value constructors fill in
+    fn form() -> PostData {
+        PostData {
+            method: DataType::FormUrlEncoded,
+            raw: "".to_string(),
+            kv_store: Vec::new(),
+            bytes: Vec::new(),
+            content_type: String::from("application/x-www-form-urlencoded"),
+            charset: None,
         }
-        return (body_type, form);
     }
 }
 
-pub struct Connection<'a> {
+/// The start of a response body for error messages, cut at `EXCERPT_LENGTH` characters
+#[cfg(feature = "json")]
+fn excerpt(body: &[u8]) -> String {
+    const EXCERPT_LENGTH: usize = 256;
+
+    let text = String::from_utf8_lossy(body);
+    match text.char_indices().nth(EXCERPT_LENGTH) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.into_owned(),
+    }
+}
+
+/// Splits the `charset` parameter off a `Content-Type` value, keeping the media type and any other parameters
+fn split_charset(content_type: String) -> (String, Option<String>) {
+    let charset = match utils::charset::charset_of(&content_type) {
+        Some(charset) => charset,
+        None => return (content_type, None),
+    };
+
+    let content_type = content_type.split(';')
+        .filter(|parameter| !parameter.split_once('=').is_some_and(|(name, _)| name.trim().eq_ignore_ascii_case("charset")))
+        .map(str::trim)
+        .collect::<Vec<&str>>()
+        .join("; ");
+    (content_type, Some(charset))
+}
+
+/// A connection to a single host, kept open by the client's pool between requests
+pub struct Connection {
     pub is_secure: bool,
     pub domain: String,
     pub port: usize,
-    pub in_use: bool,
-    pub stream: TcpStream,
-    pub tls: Option<rustls::Stream<'a, rustls::ClientSession, &'a mut TcpStream>>,
+    /// The number of responses read from this connection so far
+    pub requests: usize,
+    /// When the connection was last returned to the pool
+    pub idle_since: Instant,
+    pub stream: BufReader<Box<dyn Stream>>,
 }
 
-pub struct Client<'a> {
-    pub global_headers: HashMap<String, String>,
-    pool: HashMap<u8, Connection<'a>>,
+impl Connection {
+    pub(crate) fn key(&self) -> PoolKey {
+        PoolKey::new(&self.domain, self.port, self.is_secure)
+    }
+}
+
+/// Sends requests over a pool of keep-alive connections, applying the same configuration and global headers to each of them
+pub struct Client {
+    pub global_headers: HeaderMap,
+    pool: Pool,
     pub config: ClientConfig,
+    transport: Arc<dyn Transport>,
+    cookies: CookieJar,
+}
+
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+impl Client {
+    pub fn new() -> Client {
+        Client::with_transport(NetTransport)
+    }
 
-impl<'a> Client<'a> {
-    pub fn new() -> Client<'a> {
+    /// Creates a client which opens its connections through `transport`, for example a `MockTransport` in tests
+    pub fn with_transport<T: Transport + 'static>(transport: T) -> Client {
         Client {
-            global_headers: HashMap::new(),
-            pool: HashMap::new(),
-            config: ClientConfig {
-                no_parse: false,
-                force_https: false,
-                redirect_limit: 10,
-                auto_upgrade: true,
-                max_queue_length: 10,
-                perform_preflight: true,
-                connection_limit: 5,
-                cycle_connections: false,
-            },
+            global_headers: HeaderMap::new(),
+            pool: Pool::new(),
+            config: ClientConfig::default(),
+            transport: Arc::new(transport),
+            cookies: CookieJar::new(),
+        }
+    }
+
+    /// Sends `request` through the client's transport, following the client's configuration.
+    /// Headers in `global_headers` are added to the request unless it already sets a header with the same name.
+    /// When `config.error_for_status` is set, `4xx` and `5xx` responses are returned as errors.
+    pub fn send(&mut self, request: &Request) -> Result<Response> {
+        let request = self.with_global_headers(request);
+
+        let response = request.execute(&self.pool, self.transport.as_ref(), &self.config, self.cookie_jar())?;
+        if self.config.error_for_status {
+            return response.error_for_status();
         }
+
+        Ok(response)
     }
 
-    fn send(mut self, request: Request) {
-        if self.pool.len() == 0 {
-            println!("{:#?}", request);
-            // let connection = Connection {
-            //     is_secure: false,
-            //     domain: request.domain,
-            //     port: 80,
-            //     in_use: false,
-            //     stream: TcpStream(format!("{}:{}", self.domain, self.port)),
-            //     tls: None,
-            // };
-            // self.pool.insert(1, connection);
-            // request.send(self.pool.get_mut(&1).unwrap());
+    /// Sends `request` like `send`, but returns as soon as the response head has been read so the body can be streamed.
+    /// The connection goes back to the client's pool once the body has been read to the end.
+    /// When `config.error_for_status` is set, the body of a `4xx` or `5xx` response is read into the returned error.
+    pub fn send_streaming(&mut self, request: &Request) -> Result<StreamingResponse> {
+        let request = self.with_global_headers(request);
+
+        let streaming = request.execute_streaming(&self.pool, self.transport.as_ref(), &self.config, self.cookie_jar())?;
+        if self.config.error_for_status && matches!(streaming.response.status, Some(400..=599)) {
+            return Err(streaming.into_response()?.error_for_status().unwrap_err());
         }
+
+        Ok(streaming)
+    }
+
+    /// The number of open connections waiting in the client's pool for another request
+    pub fn idle_connections(&self) -> usize {
+        self.pool.idle_count()
+    }
+
+    /// Closes every idle connection in the client's pool
+    pub fn close_idle_connections(&mut self) {
+        self.pool.clear();
+    }
+
+    /// The cookies the client has stored, which are sent with every request they match while `config.store_cookies` is set
+    pub fn cookies(&self) -> &CookieJar {
+        &self.cookies
+    }
+
+    /// Replaces the client's cookie jar, for example with a clone of another client's jar so both share the same cookies
+    pub fn set_cookie_jar(&mut self, jar: CookieJar) {
+        self.cookies = jar;
+    }
+
+    // every value of a global header is added, unless the request sets a header with the same name itself
+    fn with_global_headers(&self, request: &Request) -> Request {
+        let mut merged = request.clone();
+        for (name, value) in self.global_headers.iter() {
+            if !request.headers.contains(name) {
+                merged.append_header(name, value);
+            }
+        }
+        merged
+    }
+
+    fn cookie_jar(&self) -> Option<&CookieJar> {
+        Some(&self.cookies).filter(|_| self.config.store_cookies)
     }
 
     pub fn get<S: Into<String>>(&mut self, uri: S) -> Request {
@@ -554,7 +1652,19 @@ impl<'a> Client<'a> {
     pub fn post<S: Into<String>>(&mut self, uri: S, body: &PostData) -> Request {
         let mut r = Request::post(uri.into());
         r.set_body(body);
-        return r;
+        r
+    }
+
+    pub fn put<S: Into<String>>(&mut self, uri: S, body: &PostData) -> Request {
+        let mut r = Request::put(uri.into());
+        r.set_body(body);
+        r
+    }
+
+    pub fn patch<S: Into<String>>(&mut self, uri: S, body: &PostData) -> Request {
+        let mut r = Request::patch(uri.into());
+        r.set_body(body);
+        r
     }
 
     pub fn delete<S: Into<String>>(&mut self, uri: S) -> Request {
@@ -568,6 +1678,4 @@ impl<'a> Client<'a> {
     pub fn options<S: Into<String>>(&mut self, uri: S) -> Request {
         Request::options(uri.into())
     }
-
-    //pub fn connect
 }
\ No newline at end of file
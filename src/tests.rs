@@ -1,4 +1,37 @@
 mod parsers;
+mod codec;
 mod tcp_methods;
 mod tls_methods;
-mod request_builder;
\ No newline at end of file
+mod request_builder;
+mod pool;
+mod errors;
+mod status_errors;
+mod timeouts;
+mod redirects;
+mod streaming;
+mod encoding;
+mod multipart;
+mod cookies;
+mod headers;
+#[cfg(feature = "json")]
+mod json;
+
+use crate::structs::Client;
+use crate::transport::MockTransport;
+
+/// A client which answers from `scripts`, one connection per script, with preflight requests turned off
+pub(crate) fn mock_client<S: AsRef<[u8]>>(scripts: &[S]) -> (Client, MockTransport) {
+    let mock = MockTransport::new();
+    for script in scripts {
+        mock.push_response(script.as_ref());
+    }
+
+    let mut client = Client::with_transport(mock.clone());
+    client.config.perform_preflight = false;
+    (client, mock)
+}
+
+/// Everything written on connection `index` of `mock`, as text
+pub(crate) fn sent(mock: &MockTransport, index: usize) -> String {
+    String::from_utf8(mock.requests()[index].clone()).unwrap()
+}
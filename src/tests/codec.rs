@@ -0,0 +1,185 @@
+use crate::codec::serialize_request;
+use crate::structs::{PostData, Request, Response};
+use crate::types::{Error, Result};
+
+use super::mock_client;
+
+const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+
+/// Sends `request` to a server which answers with `script`, reading the body through the `BodyReader` of a streaming response
+fn read(request: &Request, script: &[u8]) -> Result<Response> {
+    let (mut client, _) = mock_client(&[script]);
+    client.send_streaming(request)?.into_response()
+}
+
+fn get(script: &[u8]) -> Result<Response> {
+    read(&Request::get("http://example.com/"), script)
+}
+
+/// Reads the response to a GET request, then checks the connection went back to the pool positioned at the next response
+fn get_then_reuse(script: &[u8]) -> Response {
+    let (mut client, mock) = mock_client(&[[script, OK.as_bytes()].concat()]);
+
+    let response = client.send_streaming(&Request::get("http://example.com/")).unwrap().into_response().unwrap();
+    assert_eq!(client.idle_connections(), 1);
+    assert_eq!(client.send(&Request::get("http://example.com/next")).unwrap().text(), "ok");
+    assert_eq!(mock.connections().len(), 1);
+    response
+}
+
+#[test]
+fn test_serialize_request() {
+    let mut request = Request::post("http://localhost:8080/documents");
+    request.set_header("X-Trace-Id", "abc");
+    request.set_body(&PostData::from_str("hello"));
+
+    let bytes = serialize_request(&request, 80).unwrap();
+
+    assert_eq!(String::from_utf8(bytes).unwrap(), "POST /documents HTTP/1.1\r\nUser-Agent: Curio/0.1.0\r\nHost: localhost:8080\r\nConnection: Keep-Alive\r\nAccept: application/json\r\nContent-Length: 5\r\nContent-Type: text/plain; charset=utf-8\r\nX-Trace-Id: abc\r\n\r\nhello");
+}
+
+#[test]
+fn test_serialize_custom_method_has_no_accept_header() {
+    let bytes = serialize_request(&Request::custom("PROPFIND", "http://localhost/documents"), 80).unwrap();
+
+    assert_eq!(String::from_utf8(bytes).unwrap(), "PROPFIND /documents HTTP/1.1\r\nUser-Agent: Curio/0.1.0\r\nHost: localhost\r\nConnection: Keep-Alive\r\n\r\n");
+}
+
+#[test]
+fn test_read_content_length_body() {
+    let response = get_then_reuse(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+
+    assert_eq!(response.status, Some(200));
+    assert_eq!(response.bytes(), b"hello");
+}
+
+#[test]
+fn test_read_lowercase_content_length_body() {
+    let response = get_then_reuse(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nhello");
+
+    assert_eq!(response.bytes(), b"hello");
+}
+
+#[test]
+fn test_read_truncated_body() {
+    assert!(matches!(get(b"HTTP/1.1 200 OK\r\nContent-Length: 50\r\n\r\nhello"), Err(Error::MalformedResponse { .. })));
+}
+
+#[test]
+fn test_read_chunked_body_with_blank_lines() {
+    let response = get_then_reuse(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n8\r\nab\r\n\r\ncd\r\n3\r\nefg\r\n0\r\n\r\n");
+
+    assert_eq!(response.bytes(), b"ab\r\n\r\ncdefg");
+}
+
+#[test]
+fn test_read_close_delimited_body() {
+    let (mut client, _) = mock_client(&["HTTP/1.1 200 OK\r\n\r\nuntil the end"]);
+    let response = client.send_streaming(&Request::get("http://example.com/")).unwrap().into_response().unwrap();
+
+    assert_eq!(response.bytes(), b"until the end");
+    assert_eq!(client.idle_connections(), 0);
+}
+
+#[test]
+fn test_read_head_response_has_no_body() {
+    let (mut client, mock) = mock_client(&[["HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n", OK].concat()]);
+    let response = client.send_streaming(&Request::head("http://example.com/")).unwrap().into_response().unwrap();
+
+    assert_eq!(response.body, None);
+    assert_eq!(client.send(&Request::get("http://example.com/next")).unwrap().text(), "ok");
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[test]
+fn test_read_no_content_and_not_modified_have_no_body() {
+    for status in ["204 No Content", "304 Not Modified"].iter() {
+        let response = get_then_reuse(format!("HTTP/1.1 {}\r\nContent-Length: 5\r\n\r\n", status).as_bytes());
+        assert_eq!(response.body, None, "{}", status);
+    }
+}
+
+#[test]
+fn test_read_skips_interim_responses() {
+    let response = read(Request::post("http://example.com/").set_body(&PostData::from_str("hi")), b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok").unwrap();
+
+    assert_eq!(response.status, Some(201));
+    assert_eq!(response.bytes(), b"ok");
+}
+
+#[test]
+fn test_read_unterminated_head() {
+    assert!(get(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n").is_err());
+}
+
+#[test]
+fn test_read_binary_body() {
+    let response = get_then_reuse(b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 6\r\n\r\n\x89PNG\x00\xff");
+
+    assert_eq!(response.bytes(), b"\x89PNG\x00\xff");
+    assert_eq!(response.text(), "\u{FFFD}PNG\u{0}\u{FFFD}");
+}
+
+#[test]
+fn test_text_with_charset() {
+    let response = get(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=\"windows-1252\"\r\nContent-Length: 6\r\n\r\n\x93caf\xe9\x94").unwrap();
+
+    assert_eq!(response.charset().as_deref(), Some("windows-1252"));
+    assert_eq!(response.text_with_charset("utf-8").unwrap(), "\u{201C}café\u{201D}");
+}
+
+#[test]
+fn test_text_with_default_charset() {
+    let response = get(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\n\xff\xfeh\x00i\x00").unwrap();
+
+    assert_eq!(response.charset(), None);
+    assert_eq!(response.text_with_charset("utf-16").unwrap(), "hi");
+    assert!(matches!(response.text_with_charset("koi8-r"), Err(Error::UnsupportedCharset(charset)) if charset == "koi8-r"));
+}
+
+#[test]
+fn test_read_chunked_body_with_extensions_and_trailers() {
+    let response = get_then_reuse(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nTrailer: Expires\r\n\r\n5;name=value;flag\r\nhello\r\n6 ; quoted=\"a;b\"\r\n world\r\n0\r\nExpires: Wed, 21 Oct 2015 07:28:00 GMT\r\nX-Checksum: abc\r\n\r\n");
+
+    assert_eq!(response.bytes(), b"hello world");
+    assert_eq!(response.trailers.get("Expires").unwrap(), "Wed, 21 Oct 2015 07:28:00 GMT");
+    assert_eq!(response.trailers.get("X-Checksum").unwrap(), "abc");
+}
+
+#[test]
+fn test_read_invalid_chunked_bodies() {
+    let invalid: [&[u8]; 5] = [
+        b"zz\r\nhello\r\n0\r\n\r\n",
+        b"3\r\nhello\r\n0\r\n\r\n",
+        b"5\r\nhel",
+        b"10000000000000000\r\n",
+        b"0\r\nnot a trailer\r\n\r\n",
+    ];
+
+    for body in invalid.iter() {
+        let script = [&b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n"[..], body].concat();
+        assert!(matches!(get(&script), Err(Error::MalformedResponse { .. })), "{:?}", String::from_utf8_lossy(body));
+    }
+}
+
+#[test]
+fn test_read_chunked_size_line_limit() {
+    let extension = "x".repeat(crate::codec::chunked::MAX_CHUNK_LINE);
+    let script = format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5;{}\r\nhello\r\n0\r\n\r\n", extension);
+
+    assert!(get(script.as_bytes()).is_err());
+}
+
+#[test]
+fn test_read_chunked_trailer_size_limit() {
+    let trailers = |size: usize| {
+        let field = format!("X-Padding: {}\r\n", "x".repeat(size - "X-Padding: \r\n".len()));
+        format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n0\r\n{}\r\n", field)
+    };
+
+    let response = get(trailers(crate::codec::chunked::MAX_TRAILER_SIZE).as_bytes()).unwrap();
+    assert_eq!(response.bytes(), b"ok");
+    assert!(response.trailers.contains("X-Padding"));
+
+    assert!(matches!(get(trailers(crate::codec::chunked::MAX_TRAILER_SIZE + 1).as_bytes()), Err(Error::MalformedResponse { .. })));
+}
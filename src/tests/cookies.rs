@@ -0,0 +1,247 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cookies::{CookieJar, SameSite};
+use crate::structs::{Cookie, PostData, Request};
+use crate::utils::dates::parse_cookie_date;
+use crate::utils::parsers::parse_cookie;
+
+use super::{mock_client, sent};
+
+fn cookie(line: &str) -> Cookie {
+    parse_cookie(format!("Set-Cookie: {}", line)).unwrap()
+}
+
+fn names(jar: &CookieJar, url: &str) -> Vec<String> {
+    jar.matching(url).into_iter().map(|cookie| cookie.name).collect()
+}
+
+#[test]
+fn test_cookie_dates() {
+    let expected = UNIX_EPOCH + Duration::from_secs(784111777);
+    for date in ["Sun, 06 Nov 1994 08:49:37 GMT", "Sunday, 06-Nov-94 08:49:37 GMT", "Sun Nov  6 08:49:37 1994"].iter() {
+        assert_eq!(parse_cookie_date(date), Some(expected), "{}", date);
+    }
+
+    assert_eq!(parse_cookie_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(UNIX_EPOCH));
+    assert_eq!(parse_cookie_date("Tue, 29 Feb 2000 12:00:00 GMT"), Some(UNIX_EPOCH + Duration::from_secs(951825600)));
+    assert_eq!(parse_cookie_date("Wed, 29 Feb 2001 12:00:00 GMT"), None);
+    assert_eq!(parse_cookie_date("Wed, 21 Oct 2015 25:28:00 GMT"), None);
+    assert_eq!(parse_cookie_date("21 Oct 2015"), None);
+    assert_eq!(parse_cookie_date("tomorrow"), None);
+}
+
+#[test]
+fn test_cookie_domain_matching() {
+    let jar = CookieJar::new();
+    assert!(jar.store("http://www.example.com/", &cookie("host=1")));
+    assert!(jar.store("http://www.example.com/", &cookie("shared=2; Domain=.Example.com")));
+    assert!(!jar.store("http://www.example.com/", &cookie("other=3; Domain=example.org")));
+    assert!(!jar.store("http://www.example.com/", &cookie("tld=4; Domain=com")));
+    assert!(!jar.store("http://example.com/", &cookie("child=5; Domain=www.example.com")));
+
+    assert_eq!(names(&jar, "http://www.example.com/"), vec!["host", "shared"]);
+    assert_eq!(names(&jar, "http://api.example.com/"), vec!["shared"]);
+    assert_eq!(names(&jar, "http://example.com/"), vec!["shared"]);
+    assert!(names(&jar, "http://notexample.com/").is_empty());
+
+    let shared = &jar.matching("http://example.com/")[0];
+    assert_eq!((shared.domain.as_str(), shared.host_only), ("example.com", false));
+}
+
+#[test]
+fn test_cookie_path_matching() {
+    let jar = CookieJar::new();
+    jar.store("http://example.com/docs/guide/intro", &cookie("default=1"));
+    jar.store("http://example.com/", &cookie("docs=2; Path=/docs"));
+    jar.store("http://example.com/", &cookie("root=3; Path=/"));
+
+    assert_eq!(jar.cookies()[0].path, "/docs/guide");
+    assert_eq!(names(&jar, "http://example.com/docs/guide/intro?page=1"), vec!["default", "docs", "root"]);
+    assert_eq!(names(&jar, "http://example.com/docs"), vec!["docs", "root"]);
+    assert_eq!(names(&jar, "http://example.com/docsearch"), vec!["root"]);
+}
+
+#[test]
+fn test_cookie_secure_and_http_only() {
+    let jar = CookieJar::new();
+    assert!(!jar.store("http://example.com/", &cookie("token=1; Secure")));
+    assert!(jar.store("https://example.com/", &cookie("token=1; Secure")));
+    assert!(!jar.store("http://example.com/", &cookie("token=2")));
+    assert!(jar.store("https://example.com/", &cookie("session=a; HttpOnly")));
+
+    assert_eq!(names(&jar, "https://example.com/"), vec!["token", "session"]);
+    assert_eq!(names(&jar, "http://example.com/"), vec!["session"]);
+    assert_eq!(jar.matching_non_http("https://example.com/").into_iter().map(|cookie| cookie.name).collect::<Vec<String>>(), vec!["token"]);
+
+    assert!(!jar.store_non_http("https://example.com/", &cookie("script=1; HttpOnly")));
+    assert!(!jar.store_non_http("https://example.com/", &cookie("session=b")));
+    assert_eq!(jar.header("https://example.com/"), Some(String::from("token=1; session=a")));
+}
+
+#[test]
+fn test_cookie_prefixes() {
+    let jar = CookieJar::new();
+    assert!(!jar.store("https://example.com/", &cookie("__Secure-id=1")));
+    assert!(jar.store("https://example.com/", &cookie("__Secure-id=1; Secure; Domain=example.com")));
+    assert!(!jar.store("https://example.com/", &cookie("__Host-id=2; Secure; Domain=example.com; Path=/")));
+    assert!(!jar.store("https://example.com/", &cookie("__Host-id=2; Secure; Path=/app")));
+    assert!(jar.store("https://example.com/", &cookie("__Host-id=2; Secure; Path=/")));
+}
+
+#[test]
+fn test_cookie_expiry() {
+    let jar = CookieJar::new();
+    jar.store("http://example.com/", &cookie("a=1"));
+    jar.store("http://example.com/", &cookie("b=2; Max-Age=3600"));
+    jar.store("http://example.com/", &cookie("c=3; Expires=Wed, 01 Jan 2200 00:00:00 GMT"));
+    assert_eq!(jar.len(), 3);
+
+    let expires = jar.matching("http://example.com/")[1].expires.unwrap();
+    assert!(expires > SystemTime::now() + Duration::from_secs(3590));
+
+    assert!(!jar.store("http://example.com/", &cookie("a=; Max-Age=0")));
+    assert!(!jar.store("http://example.com/", &cookie("c=; Expires=Thu, 01 Jan 1970 00:00:00 GMT")));
+    // Max-Age wins over Expires
+    jar.store("http://example.com/", &cookie("b=4; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=60"));
+
+    assert_eq!(jar.header("http://example.com/"), Some(String::from("b=4")));
+}
+
+#[test]
+fn test_cookie_max_age_overflow_is_clamped() {
+    let jar = CookieJar::new();
+    assert!(jar.store("http://example.com/", &cookie("forever=1; Max-Age=9223372036854775807")));
+    assert!(jar.store("http://example.com/", &cookie("beyond=1; Max-Age=99999999999999999999999")));
+
+    let latest = UNIX_EPOCH + Duration::from_secs(253402300799);
+    for stored in jar.cookies() {
+        assert_eq!(stored.expires, Some(latest), "{}", stored.name);
+    }
+
+    let mut saved = Vec::new();
+    jar.save_netscape(&mut saved).unwrap();
+    assert!(String::from_utf8(saved).unwrap().contains("\tFALSE\t/\tFALSE\t253402300799\tforever\t1\n"));
+}
+
+#[test]
+fn test_cookie_same_site() {
+    let jar = CookieJar::new();
+    assert!(!jar.store("https://example.com/", &cookie("none=0; SameSite=None")));
+    jar.store("https://example.com/", &cookie("strict=1; SameSite=Strict"));
+    jar.store("https://example.com/", &cookie("lax=2; SameSite=lax"));
+    jar.store("https://example.com/", &cookie("none=3; SameSite=None; Secure"));
+    assert_eq!(jar.cookies()[1].same_site, Some(SameSite::Lax));
+
+    let (mut client, mock) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".repeat(4)]);
+    client.set_cookie_jar(jar.clone());
+
+    client.send(Request::get("https://example.com/").set_site_for_cookies("https://www.example.com/")).unwrap();
+    client.send(Request::get("https://example.com/").set_site_for_cookies("https://other.org/")).unwrap();
+    client.send(Request::post("https://example.com/").set_site_for_cookies("https://other.org/")).unwrap();
+    client.send(Request::get("https://example.com/").set_site_for_cookies("http://example.com/")).unwrap();
+
+    let sent = sent(&mock, 0);
+    let cookies = sent.lines().filter(|line| line.starts_with("Cookie: ") || line.starts_with("GET ") || line.starts_with("POST ")).collect::<Vec<&str>>();
+    assert_eq!(cookies, vec![
+        "GET / HTTP/1.1", "Cookie: strict=1; lax=2; none=3",
+        "GET / HTTP/1.1", "Cookie: lax=2; none=3",
+        "POST / HTTP/1.1", "Cookie: none=3",
+        "GET / HTTP/1.1", "Cookie: lax=2; none=3",
+    ]);
+}
+
+#[test]
+fn test_client_stores_and_sends_cookies() {
+    let (mut client, mock) = mock_client(&[[
+        "HTTP/1.1 200 OK\r\nSet-Cookie: session=abc; Path=/; HttpOnly\r\nSet-Cookie: theme=dark; Path=/app\r\nContent-Length: 0\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nSet-Cookie: session=; Max-Age=0; Path=/\r\nContent-Length: 0\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+    ].concat()]);
+
+    client.send(Request::post("http://example.com/login").set_body(&PostData::from_str("user=curio"))).unwrap();
+    client.send(Request::get("http://example.com/app/settings").set_header("Cookie", "own=1")).unwrap();
+    client.send(&Request::get("http://example.com/logout")).unwrap();
+    client.send(&Request::get("http://example.com/app/")).unwrap();
+
+    let sent = sent(&mock, 0);
+    let cookies = sent.lines().filter(|line| line.starts_with("Cookie")).collect::<Vec<&str>>();
+    assert_eq!(cookies, vec!["Cookie: own=1; theme=dark; session=abc", "Cookie: session=abc", "Cookie: theme=dark"]);
+    assert_eq!(client.cookies().len(), 1);
+}
+
+#[test]
+fn test_cookies_follow_redirects() {
+    let (mut client, mock) = mock_client(&[[
+        "HTTP/1.1 302 Found\r\nLocation: /home\r\nSet-Cookie: session=abc\r\nContent-Length: 0\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+    ].concat()]);
+
+    client.send(&Request::get("http://example.com/login")).unwrap();
+    let sent = sent(&mock, 0);
+    let (first, second) = sent.split_at(sent.find("GET /home HTTP/1.1\r\n").unwrap());
+    assert!(!first.contains("Cookie"));
+    assert!(second.contains("\r\nCookie: session=abc\r\n"));
+}
+
+#[test]
+fn test_cookie_store_can_be_disabled() {
+    let (mut client, mock) = mock_client(&["HTTP/1.1 200 OK\r\nSet-Cookie: a=1\r\nContent-Length: 0\r\n\r\n".repeat(2)]);
+    client.config.store_cookies = false;
+
+    client.send(&Request::get("http://example.com/")).unwrap();
+    client.send(&Request::get("http://example.com/")).unwrap();
+    assert!(client.cookies().is_empty());
+    assert!(!sent(&mock, 0).contains("Cookie"));
+}
+
+#[test]
+fn test_cookie_jar_netscape_round_trip() {
+    let jar = CookieJar::new();
+    jar.store("https://www.example.com/account/login", &cookie("session=abc; HttpOnly; Secure"));
+    jar.store("https://www.example.com/", &cookie("theme=dark; Domain=example.com; Path=/; Max-Age=3600"));
+
+    let mut saved = Vec::new();
+    jar.save_netscape(&mut saved).unwrap();
+    let saved = String::from_utf8(saved).unwrap();
+    assert!(saved.starts_with("# Netscape HTTP Cookie File\n"));
+    assert!(saved.contains("\n#HttpOnly_www.example.com\tFALSE\t/account\tTRUE\t0\tsession\tabc\n"));
+    assert!(saved.contains("\n.example.com\tTRUE\t/\tFALSE\t"));
+
+    let loaded = CookieJar::new();
+    assert_eq!(loaded.load_netscape(saved.as_bytes()).unwrap(), 2);
+    let (original, restored) = (jar.cookies(), loaded.cookies());
+    for (original, restored) in original.iter().zip(restored.iter()) {
+        assert_eq!((&original.name, &original.value, &original.domain, original.host_only), (&restored.name, &restored.value, &restored.domain, restored.host_only));
+        assert_eq!((&original.path, original.secure, original.http_only), (&restored.path, restored.secure, restored.http_only));
+    }
+    assert_eq!(restored[0].expires, None);
+    let seconds = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap().as_secs();
+    assert_eq!(restored[1].expires.map(seconds), original[1].expires.map(seconds));
+}
+
+#[test]
+fn test_cookie_jar_loads_curl_files() {
+    let file = [
+        "# Netscape HTTP Cookie File",
+        "# https://curl.se/docs/http-cookies.html",
+        "",
+        ".example.com\tTRUE\t/\tFALSE\t4102444800\tlasting\t1",
+        "example.com\tFALSE\t/app\tFALSE\t0\tsession\t2\r",
+        "#HttpOnly_example.com\tFALSE\t/\tTRUE\t0\ttoken\t3",
+        "example.com\tFALSE\t/\tFALSE\t1\texpired\t4",
+        "example.com\tFALSE\t/\tFALSE\t0\tempty",
+        "example.com\tMAYBE\t/\tFALSE\t0\tbroken\t5",
+        "example.com\tFALSE\t/\tFALSE\tsoon\tbroken\t6",
+        "not a cookie line",
+    ].join("\n");
+
+    let jar = CookieJar::new();
+    jar.store("http://example.com/", &cookie("lasting=old; Domain=example.com"));
+    assert_eq!(jar.load_netscape(file.as_bytes()).unwrap(), 4);
+    assert_eq!(jar.header("https://example.com/app/"), Some(String::from("session=2; lasting=1; token=3; empty=")));
+    assert!(jar.matching_non_http("https://example.com/").iter().all(|cookie| cookie.name != "token"));
+
+    jar.clear_session_cookies();
+    assert_eq!(jar.header("https://example.com/app/"), Some(String::from("lasting=1")));
+}
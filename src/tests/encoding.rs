@@ -0,0 +1,355 @@
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+use std::io::Read;
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli"))]
+use std::io::Write;
+
+use crate::structs::Request;
+
+use super::{mock_client, sent};
+
+/// A response whose `encoding` body is sent in two chunks, followed by trailers and a second response on the same connection
+#[cfg(any(feature = "gzip", feature = "deflate", feature = "brotli"))]
+fn chunked_encoded_response(encoding: &str, body: &[u8]) -> Vec<u8> {
+    let (first, second) = body.split_at(body.len() / 2);
+    [
+        format!("HTTP/1.1 200 OK\r\nContent-Encoding: {}\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n", encoding, first.len()).as_bytes(),
+        first,
+        format!("\r\n{:x}\r\n", second.len()).as_bytes(),
+        second,
+        b"\r\n0\r\nX-Checksum: 42\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+    ].concat()
+}
+
+fn encoded_response(encoding: &str, body: &[u8]) -> Vec<u8> {
+    [format!("HTTP/1.1 200 OK\r\nContent-Encoding: {}\r\nContent-Length: {}\r\n\r\n", encoding, body.len()).as_bytes(), body].concat()
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(body: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(body).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn test_unsupported_encoding_is_left_alone() {
+    let (mut client, _) = mock_client(&[encoded_response("compress", b"\x1f\x9d\x90")]);
+
+    let response = client.send(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(response.bytes(), b"\x1f\x9d\x90");
+    assert_eq!(response.content_encoding, None);
+    assert_eq!(response.compressed_size, None);
+    assert_eq!(response.headers.get("Content-Encoding").unwrap(), "compress");
+}
+
+#[test]
+fn test_decompress_disabled() {
+    let (mut client, mock) = mock_client(&[encoded_response("gzip", b"not really gzip")]);
+    client.config.decompress = false;
+
+    let response = client.send(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(response.text(), "not really gzip");
+    assert!(!sent(&mock, 0).contains("Accept-Encoding"));
+}
+
+#[cfg(not(any(feature = "gzip", feature = "deflate", feature = "brotli", feature = "zstd")))]
+#[test]
+fn test_no_accept_encoding_without_features() {
+    let (mut client, mock) = mock_client(&[encoded_response("gzip", b"\x1f\x8b")]);
+
+    let response = client.send(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(response.bytes(), b"\x1f\x8b");
+    assert!(!sent(&mock, 0).contains("Accept-Encoding"));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_body_is_decoded() {
+    let compressed = gzip(b"hello hello hello hello");
+    let (mut client, mock) = mock_client(&[encoded_response("gzip", &compressed)]);
+
+    let response = client.send(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(response.text(), "hello hello hello hello");
+    assert_eq!(response.content_encoding.as_deref(), Some("gzip"));
+    assert_eq!(response.compressed_size, Some(compressed.len() as u64));
+    assert!(response.headers.get("Content-Encoding").is_none());
+    assert!(response.headers.get("Content-Length").is_none());
+
+    let sent = sent(&mock, 0);
+    assert!(sent.contains("Accept-Encoding: ") && sent.contains("gzip"));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_body_is_decoded_while_streaming() {
+    let text = b"decoded as it arrives ".repeat(200);
+    let compressed = gzip(&text);
+    let (mut client, _) = mock_client(&[encoded_response("gzip", &compressed)]);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(streaming.response.body, None);
+
+    let mut body = Vec::new();
+    let mut buffer = [0; 7];
+    loop {
+        let read = streaming.body.read(&mut buffer).unwrap();
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&buffer[..read]);
+    }
+    assert_eq!(body, text);
+    assert!(streaming.body.is_finished());
+    assert_eq!(streaming.body.compressed_size(), Some(compressed.len() as u64));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_stream_chunked_gzip_body() {
+    let compressed = gzip(&b"streamed ".repeat(100));
+    let (first, second) = compressed.split_at(compressed.len() / 2);
+    let script = [
+        format!("HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n", first.len()).as_bytes(),
+        first,
+        format!("\r\n{:x}\r\n", second.len()).as_bytes(),
+        second,
+        b"\r\n0\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+    ].concat();
+    let (mut client, mock) = mock_client(&[script]);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(streaming.response.content_encoding.as_deref(), Some("gzip"));
+
+    let mut body = Vec::new();
+    streaming.body.read_to_end(&mut body).unwrap();
+    assert_eq!(body, b"streamed ".repeat(100));
+    assert_eq!(streaming.body.compressed_size(), Some(compressed.len() as u64));
+    assert_eq!(client.idle_connections(), 1);
+
+    assert_eq!(client.send(&Request::get("http://example.com/next")).unwrap().text(), "ok");
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_chunked_gzip_body_keeps_connection_alive() {
+    let (mut client, mock) = mock_client(&[chunked_encoded_response("gzip", &gzip(b"kept alive"))]);
+
+    let response = client.send(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(response.text(), "kept alive");
+    assert_eq!(response.trailers.get("X-Checksum"), Some("42"));
+    assert_eq!(client.idle_connections(), 1);
+
+    assert_eq!(client.send(&Request::get("http://example.com/next")).unwrap().text(), "ok");
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[cfg(feature = "deflate")]
+#[test]
+fn test_chunked_deflate_body_keeps_connection_alive() {
+    let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    zlib.write_all(b"zlib wrapped").unwrap();
+    let (mut client, mock) = mock_client(&[chunked_encoded_response("deflate", &zlib.finish().unwrap())]);
+
+    let response = client.send(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(response.text(), "zlib wrapped");
+    assert_eq!(response.trailers.get("X-Checksum"), Some("42"));
+
+    assert_eq!(client.send(&Request::get("http://example.com/next")).unwrap().text(), "ok");
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[cfg(feature = "brotli")]
+#[test]
+fn test_chunked_brotli_body_keeps_connection_alive() {
+    let mut compressed = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+        encoder.write_all(&b"brotli body ".repeat(50)).unwrap();
+    }
+    let (mut client, mock) = mock_client(&[chunked_encoded_response("br", &compressed)]);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/")).unwrap();
+    let mut body = Vec::new();
+    streaming.body.read_to_end(&mut body).unwrap();
+    assert_eq!(body, b"brotli body ".repeat(50));
+    assert_eq!(streaming.body.trailers().get("X-Checksum"), Some("42"));
+    assert_eq!(client.idle_connections(), 1);
+
+    assert_eq!(client.send(&Request::get("http://example.com/next")).unwrap().text(), "ok");
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_decoded_body_limit() {
+    let (mut client, _) = mock_client(&[encoded_response("gzip", &gzip(&[b'a'; 4096]))]);
+    client.config.max_body_size = Some(1024);
+
+    assert!(matches!(client.send(&Request::get("http://example.com/")), Err(crate::types::Error::BodyTooLarge { limit: 1024 })));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_invalid_gzip_body() {
+    let (mut client, _) = mock_client(&[encoded_response("gzip", b"definitely not gzip")]);
+
+    assert!(matches!(client.send(&Request::get("http://example.com/")), Err(crate::types::Error::MalformedResponse { .. })));
+}
+
+#[cfg(feature = "deflate")]
+#[test]
+fn test_deflate_body_is_decoded() {
+    let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    zlib.write_all(b"zlib wrapped").unwrap();
+    let mut raw = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    raw.write_all(b"raw deflate").unwrap();
+    let (mut client, _) = mock_client(&[[encoded_response("deflate", &zlib.finish().unwrap()), encoded_response("deflate", &raw.finish().unwrap())].concat()]);
+
+    assert_eq!(client.send(&Request::get("http://example.com/")).unwrap().text(), "zlib wrapped");
+    assert_eq!(client.send(&Request::get("http://example.com/")).unwrap().text(), "raw deflate");
+}
+
+#[cfg(feature = "brotli")]
+#[test]
+fn test_brotli_body_is_decoded() {
+    let mut compressed = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+        encoder.write_all(b"brotli body").unwrap();
+    }
+    let (mut client, _) = mock_client(&[encoded_response("br", &compressed)]);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/")).unwrap();
+    let mut body = String::new();
+    streaming.read_to_string(&mut body).unwrap();
+    assert_eq!(body, "brotli body");
+}
+
+#[cfg(all(feature = "gzip", feature = "brotli"))]
+#[test]
+fn test_stacked_encodings_are_decoded_in_reverse() {
+    let mut compressed = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+        encoder.write_all(&gzip(b"twice encoded")).unwrap();
+    }
+    let (mut client, _) = mock_client(&[encoded_response("gzip, br", &compressed)]);
+
+    let response = client.send(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(response.text(), "twice encoded");
+    assert_eq!(response.content_encoding.as_deref(), Some("gzip, br"));
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+fn body_of(request: &[u8]) -> &[u8] {
+    let start = request.windows(4).position(|window| window == b"\r\n\r\n").unwrap() + 4;
+    &request[start..]
+}
+
+#[test]
+fn test_streamed_request_body_with_length() {
+    let (mut client, mock) = mock_client(&[b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n".to_vec()]);
+
+    let response = client.send(Request::post("http://example.com/upload").set_body_reader(std::io::Cursor::new(b"0123456789".to_vec()), "text/plain", Some(10))).unwrap();
+    assert_eq!(response.status, Some(201));
+
+    let sent = sent(&mock, 0);
+    assert!(sent.contains("Content-Length: 10\r\n"));
+    assert!(sent.contains("Content-Type: text/plain\r\n"));
+    assert!(!sent.contains("Transfer-Encoding"));
+    assert!(sent.ends_with("\r\n\r\n0123456789"));
+}
+
+#[test]
+fn test_streamed_request_body_is_chunked() {
+    let (mut client, mock) = mock_client(&[b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n".to_vec()]);
+
+    client.send(Request::put("http://example.com/upload").set_body_reader(std::io::Cursor::new(b"streamed".to_vec()), "application/octet-stream", None)).unwrap();
+
+    let sent = sent(&mock, 0);
+    assert!(sent.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(!sent.contains("Content-Length"));
+    assert!(sent.ends_with("\r\n\r\n8\r\nstreamed\r\n0\r\n\r\n"));
+}
+
+#[test]
+fn test_streamed_request_body_can_not_be_resent() {
+    let (mut client, _) = mock_client(&[b"HTTP/1.1 307 Temporary Redirect\r\nLocation: /elsewhere\r\nContent-Length: 0\r\n\r\n".to_vec()]);
+
+    let result = client.send(Request::post("http://example.com/upload").set_body_reader(std::io::Cursor::new(b"once".to_vec()), "text/plain", Some(4)));
+    assert!(matches!(result, Err(crate::types::Error::BodyNotRewindable)));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_request_body() {
+    let (mut client, mock) = mock_client(&[b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec()]);
+    let payload = "{\"event\": \"click\"}".repeat(50);
+
+    client.send(Request::post("http://example.com/telemetry")
+        .set_body(&crate::structs::PostData::from_str(payload.as_str()))
+        .set_compression(crate::structs::Compression::Gzip)).unwrap();
+
+    let sent = mock.requests()[0].clone();
+    let head = String::from_utf8_lossy(&sent);
+    let body = body_of(&sent);
+    assert!(head.contains("Content-Encoding: gzip\r\n"));
+    assert!(head.contains(&format!("Content-Length: {}\r\n", body.len())));
+    assert!(body.len() < payload.len());
+
+    let mut decoded = String::new();
+    flate2::read::GzDecoder::new(body).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, payload);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_retry_uncompressed_after_415() {
+    let refused = b"HTTP/1.1 415 Unsupported Media Type\r\nConnection: close\r\nContent-Length: 0\r\n\r\n".to_vec();
+    let retried = b"HTTP/1.1 415 Unsupported Media Type\r\nContent-Length: 0\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec();
+    let (mut client, mock) = mock_client(&[refused, retried]);
+    let mut request = Request::post("http://example.com/telemetry");
+    request.set_body(&crate::structs::PostData::from_str("plain")).set_compression(crate::structs::Compression::Gzip);
+
+    let response = client.send(&request).unwrap();
+    assert_eq!(response.status, Some(415));
+
+    let response = client.send(request.set_retry_uncompressed(true)).unwrap();
+    assert_eq!(response.status, Some(200));
+    assert_eq!(response.warnings.len(), 1);
+
+    let sent = String::from_utf8_lossy(&mock.requests()[1]).to_string();
+    let retry = &sent[sent.rfind("POST /telemetry").unwrap()..];
+    assert!(!retry.contains("Content-Encoding"));
+    assert!(retry.ends_with("\r\n\r\nplain"));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_streamed_request_body() {
+    let (mut client, mock) = mock_client(&[b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec()]);
+    let payload = b"zstd ".repeat(200);
+
+    client.send(Request::post("http://example.com/telemetry")
+        .set_body_reader(std::io::Cursor::new(payload.clone()), "text/plain", Some(payload.len() as u64))
+        .set_compression(crate::structs::Compression::Zstd)).unwrap();
+
+    let sent = mock.requests()[0].clone();
+    let head = String::from_utf8_lossy(&sent).to_string();
+    assert!(head.contains("Content-Encoding: zstd\r\n"));
+    assert!(head.contains("Transfer-Encoding: chunked\r\n"));
+
+    let mut decoder = crate::codec::ChunkedDecoder::new(body_of(&sent));
+    let mut compressed = Vec::new();
+    decoder.read_to_end(&mut compressed).unwrap();
+    assert_eq!(zstd::decode_all(&compressed[..]).unwrap(), payload);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_body_is_decoded() {
+    let (mut client, _) = mock_client(&[encoded_response("zstd", &zstd::encode_all(&b"zstd response"[..], 0).unwrap())]);
+
+    assert_eq!(client.send(&Request::get("http://example.com/")).unwrap().text(), "zstd response");
+}
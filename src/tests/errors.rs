@@ -0,0 +1,137 @@
+use std::error::Error as StdError;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use crate::structs::{Request, Response};
+use crate::transport::MockTransport;
+use crate::types::Error;
+
+use super::mock_client;
+
+#[test]
+fn test_error_connection_refused() {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+    let error = Request::get(format!("http://127.0.0.1:{}/", port)).send().unwrap_err();
+    println!("{}", error);
+    assert!(matches!(error, Error::ConnectionRefused { port: p, .. } if p == port as usize));
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn test_error_dns() {
+    let error = Request::get("http://curio.invalid/").send().unwrap_err();
+    println!("{}", error);
+    assert!(matches!(error, Error::Dns { ref host, .. } if host == "curio.invalid"));
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn test_error_tls_handshake() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut hello = [0u8; 512];
+        let _ = stream.read(&mut hello);
+        stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n").unwrap();
+    });
+
+    let error = crate::tls::connect("localhost", port as usize, None).map(|_| ()).unwrap_err();
+    server.join().unwrap();
+    println!("{}", error);
+    assert!(matches!(error, Error::TlsHandshake { .. }));
+}
+
+#[test]
+fn test_error_certificate() {
+    let invalid = std::io::Error::new(std::io::ErrorKind::InvalidData, rustls::TLSError::WebPKIError(webpki::Error::UnknownIssuer));
+    let error = crate::tls::handshake_error("example.com", invalid);
+    assert!(matches!(error, Error::Certificate { source: rustls::TLSError::WebPKIError(webpki::Error::UnknownIssuer), .. }));
+
+    let timed_out = std::io::Error::from(std::io::ErrorKind::TimedOut);
+    assert!(matches!(crate::tls::handshake_error("example.com", timed_out), Error::Timeout { .. }));
+}
+
+#[test]
+fn test_error_timeout_from_io() {
+    assert!(matches!(Error::from(std::io::Error::from(std::io::ErrorKind::TimedOut)), Error::Timeout { .. }));
+    assert!(matches!(Error::from(std::io::Error::from(std::io::ErrorKind::WouldBlock)), Error::Timeout { .. }));
+    assert!(matches!(Error::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset)), Error::Io(_)));
+}
+
+#[test]
+fn test_error_malformed_response() {
+    let responses = [
+        "garbage\r\n\r\n",
+        "HTTP/1.1 2000 OK\r\n\r\n",
+        "HTTP/1.1 abc OK\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nno colon here\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nTransfer-Encoding : chunked\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nContent-Length: ten\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort",
+        "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n",
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain",
+    ];
+
+    for response in responses.iter() {
+        let error = mock_client(&[response]).0.send(&Request::get("http://example.com/")).unwrap_err();
+        println!("{}", error);
+        assert!(matches!(error, Error::MalformedResponse { .. }), "{:?} gave {:?}", response, error);
+    }
+
+    let error = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: ten\r\n\r\n"]).0.send(&Request::get("http://example.com/")).unwrap_err();
+    assert!(error.source().unwrap().downcast_ref::<std::num::ParseIntError>().is_some());
+}
+
+#[test]
+fn test_error_empty_response_head() {
+    assert!(matches!(Response::new(String::new(), Vec::new()), Err(Error::MalformedResponse { .. })));
+}
+
+#[test]
+fn test_error_body_too_large() {
+    let responses = [
+        "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world",
+        "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nhello \r\n5\r\nworld\r\n0\r\n\r\n",
+        "HTTP/1.1 200 OK\r\n\r\nhello world",
+    ];
+
+    for response in responses.iter() {
+        let (mut client, _) = mock_client(&[response]);
+        client.config.max_body_size = Some(5);
+        let error = client.send(&Request::get("http://example.com/")).unwrap_err();
+        assert!(matches!(error, Error::BodyTooLarge { limit: 5 }), "{:?} gave {:?}", response, error);
+    }
+
+    let (mut client, _) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello"]);
+    client.config.max_body_size = Some(5);
+    assert_eq!(client.send(&Request::get("http://example.com/")).unwrap().text(), "hello");
+}
+
+#[test]
+fn test_error_invalid_url() {
+    for url in ["example.com/path", "ftp://example.com/", "http:///path", "http://example.com:99999/", "http://[::1/"].iter() {
+        let error = mock_client::<&str>(&[]).0.send(&Request::get(*url)).unwrap_err();
+        assert!(matches!(error, Error::InvalidUrl { .. }), "{:?} gave {:?}", url, error);
+    }
+}
+
+#[test]
+fn test_error_invalid_url_path() {
+    for url in ["http://example.com/a b\r\nX-Injected: 1", "http://example.com/?q=a\tb", "http://example.com/caf\u{e9}"].iter() {
+        let mock = MockTransport::new();
+        mock.push_response("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+
+        let error = Request::get(*url).send_with(&mock).unwrap_err();
+        assert!(matches!(error, Error::InvalidUrl { .. }), "{:?} gave {:?}", url, error);
+        assert!(mock.requests().is_empty());
+    }
+
+    // the path can be changed after the url was parsed
+    let mut request = Request::get("http://example.com/");
+    request.path = String::from("/a HTTP/1.1\r\nX-Injected: 1\r\n");
+    assert!(matches!(mock_client::<&str>(&[]).0.send(&request), Err(Error::InvalidUrl { .. })));
+}
@@ -0,0 +1,277 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::headers::{CacheControl, Challenge, ContentRange, ContentType, Date, ETag, HeaderMap, Link, LinkValue, RetryAfter, TypedHeader, WwwAuthenticate};
+use crate::structs::{Request, Response};
+use crate::types::{Error, Result};
+use crate::utils::dates::{format_http_date, parse_http_date};
+
+use super::{mock_client, sent};
+
+fn get(script: &str) -> Result<Response> {
+    mock_client(&[script]).0.send(&Request::get("http://example.com/"))
+}
+
+#[test]
+fn test_header_map_ignores_case() {
+    let mut headers = HeaderMap::new();
+    headers.append("Content-Type", "text/html").unwrap();
+
+    assert_eq!(headers.get("content-type"), Some("text/html"));
+    assert_eq!(headers.get("CONTENT-TYPE"), Some("text/html"));
+    assert!(headers.contains("Content-type"));
+    assert!(!headers.contains("Content-Length"));
+    assert_eq!(headers.iter().collect::<Vec<_>>(), vec![("Content-Type", "text/html")]);
+}
+
+#[test]
+fn test_header_map_keeps_order_and_values() {
+    let mut headers = HeaderMap::new();
+    headers.append("Link", "</a>; rel=next").unwrap();
+    headers.append("Accept", "text/html").unwrap();
+    headers.append("link", "</b>; rel=prev").unwrap();
+    headers.append("X-Padded", " \tvalue \t").unwrap();
+
+    assert_eq!(headers.len(), 4);
+    assert_eq!(headers.get_all("LINK"), vec!["</a>; rel=next", "</b>; rel=prev"]);
+    assert_eq!(headers.get_combined("Link").as_deref(), Some("</a>; rel=next, </b>; rel=prev"));
+    assert_eq!(headers.get("X-Padded"), Some("value"));
+    assert_eq!(headers.iter().map(|(name, _)| name).collect::<Vec<_>>(), vec!["Link", "Accept", "link", "X-Padded"]);
+
+    headers.insert("LINK", "</c>").unwrap();
+    assert_eq!(headers.iter().map(|(name, _)| name).collect::<Vec<_>>(), vec!["LINK", "Accept", "X-Padded"]);
+    assert_eq!(headers.get_all("link"), vec!["</c>"]);
+
+    assert_eq!(headers.remove("accept"), vec!["text/html"]);
+    assert!(headers.remove("accept").is_empty());
+    assert_eq!(headers.len(), 2);
+}
+
+#[test]
+fn test_header_map_rejects_invalid_fields() {
+    let mut headers = HeaderMap::new();
+
+    assert!(matches!(headers.append("Bad Name", "value"), Err(Error::InvalidHeader { .. })));
+    assert!(matches!(headers.append("", "value"), Err(Error::InvalidHeader { .. })));
+    assert!(matches!(headers.append("X-Injected", "a\r\nEvil: yes"), Err(Error::InvalidHeader { .. })));
+    assert!(matches!(headers.insert("X-Null", "a\0b"), Err(Error::InvalidHeader { .. })));
+    assert!(headers.append("X-Tabbed", "a\tb").is_ok());
+    assert_eq!(headers.len(), 1);
+}
+
+#[test]
+fn test_invalid_request_header_fails_before_sending() {
+    let (mut client, mock) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"]);
+
+    let mut request = Request::get("http://example.com/");
+    request.set_header("X-Injected", "a\r\nEvil: yes");
+
+    assert!(matches!(client.send(&request), Err(Error::InvalidHeader { .. })));
+    assert!(mock.requests().is_empty());
+}
+
+#[test]
+fn test_response_keeps_repeated_headers() {
+    let response = get("HTTP/1.1 200 OK\r\nSet-Cookie: a=1\r\nVary: Accept\r\nset-cookie: b=2; Path=/\r\nvary: Origin\r\nContent-Length: 0\r\n\r\n").unwrap();
+
+    assert_eq!(response.headers.get_all("Set-Cookie"), vec!["a=1", "b=2; Path=/"]);
+    assert_eq!(response.headers.get_combined("Vary").as_deref(), Some("Accept, Origin"));
+    assert_eq!(response.cookies.len(), 2);
+    assert_eq!(response.cookie("b").map(|cookie| cookie.value.as_str()), Some("2"));
+}
+
+#[test]
+fn test_conflicting_content_lengths_are_rejected() {
+    assert!(matches!(get("HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello!"), Err(Error::MalformedResponse { .. })));
+    assert_eq!(get("HTTP/1.1 200 OK\r\nContent-Length: 5\r\ncontent-length: 5\r\n\r\nhello").unwrap().bytes(), b"hello");
+}
+
+#[test]
+fn test_http_dates() {
+    let expected = UNIX_EPOCH + Duration::from_secs(784111777);
+    for date in ["Sun, 06 Nov 1994 08:49:37 GMT", "Sunday, 06-Nov-94 08:49:37 GMT", "Sun Nov  6 08:49:37 1994"].iter() {
+        assert_eq!(parse_http_date(date), Some(expected), "{}", date);
+    }
+
+    assert_eq!(format_http_date(expected), "Sun, 06 Nov 1994 08:49:37 GMT");
+    assert_eq!(format_http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+    assert_eq!(format_http_date(UNIX_EPOCH + Duration::from_secs(951825600)), "Tue, 29 Feb 2000 12:00:00 GMT");
+    assert_eq!(parse_http_date("Tue, 29 Feb 2000 12:00:00 GMT"), Some(UNIX_EPOCH + Duration::from_secs(951825600)));
+
+    assert_eq!(parse_http_date("Wed, 29 Feb 2001 12:00:00 GMT"), None);
+    assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 UTC"), None);
+    assert_eq!(parse_http_date("Sun, 6 Nov 1994 08:49:37 GMT"), None);
+    assert_eq!(parse_http_date("06 Nov 1994 08:49:37 GMT"), None);
+    assert_eq!(parse_http_date("tomorrow"), None);
+}
+
+#[test]
+fn test_content_type_header() {
+    let content_type = ContentType::parse("Text/HTML ; Charset=\"UTF-8\"; boundary=\"a \\\"b\\\"\"").unwrap();
+    assert_eq!(content_type.media_type, "text/html");
+    assert_eq!(content_type.charset(), Some("UTF-8"));
+    assert_eq!(content_type.boundary(), Some("a \"b\""));
+    assert_eq!(content_type.format(), "text/html; charset=UTF-8; boundary=\"a \\\"b\\\"\"");
+    assert_eq!(ContentType::parse(&content_type.format()).unwrap(), content_type);
+
+    let mut json = ContentType::new("application/json");
+    json.set_parameter("charset", "utf-8");
+    assert_eq!(json.format(), "application/json; charset=utf-8");
+
+    for invalid in ["text", "text/", "/html", "text/html; charset", "text/html; charset=\"utf-8", "text/html junk"].iter() {
+        assert!(matches!(ContentType::parse(invalid), Err(Error::InvalidHeader { .. })), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_cache_control_header() {
+    let cache_control = CacheControl::parse("public, Max-Age=3600, , s-maxage=\"60\", no-cache=\"Set-Cookie\"").unwrap();
+    assert!(cache_control.public() && cache_control.no_cache());
+    assert!(!cache_control.private() && !cache_control.no_store() && !cache_control.must_revalidate());
+    assert_eq!(cache_control.max_age(), Some(Duration::from_secs(3600)));
+    assert_eq!(cache_control.s_maxage(), Some(Duration::from_secs(60)));
+    assert_eq!(cache_control.get("no-cache"), Some("Set-Cookie"));
+    assert_eq!(cache_control.format(), "public, max-age=3600, s-maxage=60, no-cache=Set-Cookie");
+
+    let mut built = CacheControl::new();
+    built.set("no-store", None).set_max_age(Duration::from_secs(5));
+    assert_eq!(built.format(), "no-store, max-age=5");
+
+    assert_eq!(CacheControl::parse("max-age=99999999999999999999").unwrap().max_age(), Some(Duration::from_secs(2147483648)));
+    assert!(CacheControl::parse("max-stale").is_ok());
+    for invalid in ["", " , ", "max-age=soon", "max-age", "no-cache;", "private=\"unterminated"].iter() {
+        assert!(matches!(CacheControl::parse(invalid), Err(Error::InvalidHeader { .. })), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_etag_header() {
+    let strong = ETag::parse("\"33a64df5\"").unwrap();
+    let weak = ETag::parse("W/\"33a64df5\"").unwrap();
+    assert_eq!(strong, ETag::strong("33a64df5"));
+    assert_eq!(weak, ETag::weak("33a64df5"));
+    assert_eq!(weak.format(), "W/\"33a64df5\"");
+    assert_eq!(ETag::parse("\"\"").unwrap().tag, "");
+
+    assert!(strong.strong_eq(&ETag::strong("33a64df5")));
+    assert!(!strong.strong_eq(&weak) && strong.weak_eq(&weak));
+
+    for invalid in ["33a64df5", "\"33a64df5", "w/\"33a64df5\"", "\"a\"b\"", "\"a b\""].iter() {
+        assert!(matches!(ETag::parse(invalid), Err(Error::InvalidHeader { .. })), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_retry_after_header() {
+    assert_eq!(RetryAfter::parse("120").unwrap(), RetryAfter::Delay(Duration::from_secs(120)));
+    assert_eq!(RetryAfter::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap(), RetryAfter::Date(UNIX_EPOCH + Duration::from_secs(784111777)));
+    assert_eq!(RetryAfter::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap().delay(), Duration::from_secs(0));
+    assert_eq!(RetryAfter::Delay(Duration::from_secs(5)).format(), "5");
+
+    let later = SystemTime::now() + Duration::from_secs(3600);
+    assert!(RetryAfter::Date(later).delay() > Duration::from_secs(3500));
+
+    for invalid in ["", "-1", "1.5", "soon"].iter() {
+        assert!(matches!(RetryAfter::parse(invalid), Err(Error::InvalidHeader { .. })), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_content_range_header() {
+    assert_eq!(ContentRange::parse("bytes 0-499/1234").unwrap(), ContentRange::bytes(0, 499, Some(1234)));
+    assert_eq!(ContentRange::parse("bytes 500-999/*").unwrap(), ContentRange::bytes(500, 999, None));
+    assert_eq!(ContentRange::parse("bytes */1234").unwrap(), ContentRange::unsatisfied(1234));
+    assert_eq!(ContentRange::bytes(0, 0, None).format(), "bytes 0-0/*");
+    assert_eq!(ContentRange::unsatisfied(10).format(), "bytes */10");
+
+    for invalid in ["bytes", "bytes 0-499", "bytes */*", "bytes 5-4/10", "bytes 0-10/10", "bytes -5/10", "bytes 0-4/ten"].iter() {
+        assert!(matches!(ContentRange::parse(invalid), Err(Error::InvalidHeader { .. })), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_link_header() {
+    let link = Link::parse("<https://api.example.com/items?page=2>; rel=\"next\", </items?page=9>;rel=last; title=\"Last, final\", <https://example.com/>; rel=\"Alternate Home\"; hreflang=en; crossorigin").unwrap();
+    assert_eq!(link.links.len(), 3);
+    assert_eq!(link.find("next").map(|link| link.target.as_str()), Some("https://api.example.com/items?page=2"));
+    assert_eq!(link.find("last").and_then(|link| link.parameter("title")), Some("Last, final"));
+    assert_eq!(link.find("home").map(|link| link.rels()), Some(vec!["Alternate", "Home"]));
+    assert_eq!(link.links[2].parameter("crossorigin"), Some(""));
+    assert!(link.find("prev").is_none());
+
+    let mut built = Link::new();
+    let mut next = LinkValue::new("/items?page=3");
+    next.set_parameter("rel", "next");
+    let mut docs = LinkValue::new("/docs");
+    docs.set_parameter("rel", "help describedby");
+    built.push(next).push(docs);
+    assert_eq!(built.format(), "</items?page=3>; rel=next, </docs>; rel=\"help describedby\"");
+    assert_eq!(Link::parse(&built.format()).unwrap(), built);
+
+    for invalid in ["https://example.com/", "<https://example.com/", "<https://example.com/> rel=next", "</a>; rel=\"next"].iter() {
+        assert!(matches!(Link::parse(invalid), Err(Error::InvalidHeader { .. })), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_www_authenticate_header() {
+    let header = WwwAuthenticate::parse("Newauth realm=\"apps\", type=1, title=\"Login to \\\"apps\\\"\", Basic realm=\"simple\", Negotiate a87421000492aa874209af8bc028==, Bearer").unwrap();
+    let schemes = header.challenges.iter().map(|challenge| challenge.scheme.as_str()).collect::<Vec<&str>>();
+    assert_eq!(schemes, vec!["Newauth", "Basic", "Negotiate", "Bearer"]);
+
+    let newauth = &header.challenges[0];
+    assert_eq!(newauth.realm(), Some("apps"));
+    assert_eq!(newauth.parameter("TYPE"), Some("1"));
+    assert_eq!(newauth.parameter("title"), Some("Login to \"apps\""));
+    assert_eq!(header.find("basic").and_then(|challenge| challenge.realm()), Some("simple"));
+    assert_eq!(header.find("Negotiate").and_then(|challenge| challenge.token68.as_deref()), Some("a87421000492aa874209af8bc028=="));
+    assert!(header.find("Bearer").map(|challenge| challenge.parameters.is_empty()).unwrap());
+
+    let mut built = WwwAuthenticate::new();
+    let mut bearer = Challenge::new("Bearer");
+    bearer.set_parameter("realm", "api").set_parameter("error", "invalid_token");
+    built.push(Challenge::new("Basic")).push(bearer);
+    assert_eq!(built.format(), "Basic, Bearer realm=\"api\", error=\"invalid_token\"");
+    assert_eq!(WwwAuthenticate::parse(&built.format()).unwrap(), built);
+
+    for invalid in ["\"Basic\"", "Basic realm=\"a\", title=", "Basic realm=\"a\" junk", "Basic realm=\"unterminated"].iter() {
+        assert!(matches!(WwwAuthenticate::parse(invalid), Err(Error::InvalidHeader { .. })), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_date_header() {
+    let date = Date::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+    assert_eq!(date, Date(UNIX_EPOCH + Duration::from_secs(784111777)));
+    assert_eq!(date.format(), "Sun, 06 Nov 1994 08:49:37 GMT");
+    assert!(matches!(Date::parse("1994-11-06T08:49:37Z"), Err(Error::InvalidHeader { .. })));
+}
+
+#[test]
+fn test_typed_headers_on_responses_and_requests() {
+    let response = get("HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"one\"\r\nCache-Control: no-store\r\nwww-authenticate: Bearer realm=\"two\"\r\ncache-control: max-age=0\r\nETag: not-quoted\r\nContent-Length: 0\r\n\r\n").unwrap();
+
+    let challenges = response.typed_header::<WwwAuthenticate>().unwrap().unwrap();
+    assert_eq!(challenges.find("Bearer").and_then(|challenge| challenge.realm()), Some("two"));
+    let cache_control = response.typed_header::<CacheControl>().unwrap().unwrap();
+    assert!(cache_control.no_store());
+    assert_eq!(cache_control.max_age(), Some(Duration::from_secs(0)));
+    assert!(response.typed_header::<RetryAfter>().unwrap().is_none());
+    assert!(matches!(response.typed_header::<ETag>(), Err(Error::InvalidHeader { .. })));
+
+    let (mut client, mock) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"]);
+
+    let mut content_type = ContentType::new("text/plain");
+    content_type.set_parameter("charset", "utf-8");
+    let mut request = Request::get("http://example.com/");
+    request.set_typed_header(&content_type).set_typed_header(&Date(UNIX_EPOCH));
+    client.send(&request).unwrap();
+
+    let sent = sent(&mock, 0);
+    assert!(sent.contains("\r\nContent-Type: text/plain; charset=utf-8\r\n"), "{}", sent);
+    assert!(sent.contains("\r\nDate: Thu, 01 Jan 1970 00:00:00 GMT\r\n"), "{}", sent);
+    assert_eq!(request.headers.typed::<ContentType>().unwrap(), Some(content_type));
+
+    let mut headers = HeaderMap::new();
+    headers.insert_typed(&ETag::weak("v1")).unwrap();
+    assert_eq!(headers.get("etag"), Some("W/\"v1\""));
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::structs::{PostData, Request, Response};
+use crate::types::Error;
+
+use super::{mock_client, sent};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Post {
+    id: u32,
+    title: String,
+    tags: Vec<String>,
+}
+
+fn response(status: &str, body: &str) -> Response {
+    let head = vec![format!("HTTP/1.1 {}", status), String::from("Content-Type: application/json")];
+    Response::new(body, head).unwrap()
+}
+
+#[test]
+fn test_json_request_body() {
+    let created = "{\"id\":7,\"title\":\"Curio\",\"tags\":[\"é\"]}";
+    let (mut client, mock) = mock_client(&[format!("HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", created.len(), created)]);
+
+    let post = Post { id: 0, title: String::from("Curio"), tags: vec![String::from("é")] };
+    let response = client.send(Request::post("http://example.com/posts").set_json(&post).unwrap()).unwrap();
+
+    let sent = sent(&mock, 0);
+    assert!(sent.contains("Content-Type: application/json\r\n"));
+    assert!(sent.ends_with("\r\n\r\n{\"id\":0,\"title\":\"Curio\",\"tags\":[\"é\"]}"));
+    assert!(sent.contains(&format!("Content-Length: {}\r\n", "{\"id\":0,\"title\":\"Curio\",\"tags\":[\"é\"]}".len())));
+
+    let created: Post = response.json().unwrap();
+    assert_eq!(created, Post { id: 7, title: String::from("Curio"), tags: vec![String::from("é")] });
+}
+
+#[test]
+fn test_json_response_with_byte_order_mark() {
+    let value: Vec<u8> = response("200 OK", "\u{FEFF}[1, 2, 3]").json().unwrap();
+    assert_eq!(value, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_json_error_has_status_and_excerpt() {
+    let error = response("502 Bad Gateway", "<html><body>upstream unavailable</body></html>").json::<Post>().unwrap_err();
+
+    assert_eq!(error.status(), Some(502));
+    match &error {
+        Error::Json { status, excerpt, .. } => {
+            assert_eq!(*status, Some(502));
+            assert_eq!(excerpt, "<html><body>upstream unavailable</body></html>");
+        }
+        other => panic!("expected a JSON error, got {:?}", other),
+    }
+    let message = error.to_string();
+    assert!(message.contains("502"));
+    assert!(message.contains("upstream unavailable"));
+    assert!(std::error::Error::source(&error).is_some());
+
+    let long = format!("{{\"id\": \"{}\"}}", "é".repeat(1000));
+    match response("200 OK", &long).json::<Post>().unwrap_err() {
+        Error::Json { excerpt, .. } => {
+            assert_eq!(excerpt.chars().count(), 259);
+            assert!(excerpt.ends_with("é..."));
+        }
+        other => panic!("expected a JSON error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_json_serialize_error() {
+    let mut map = BTreeMap::new();
+    map.insert(vec![1u8], 1);
+
+    let error = PostData::json(&map).unwrap_err();
+    assert!(matches!(error, Error::Json { status: None, .. }));
+    assert_eq!(error.status(), None);
+}
+
+#[test]
+fn test_cookie_jar_json_round_trip() {
+    use crate::cookies::{CookieJar, SameSite};
+    use crate::utils::parsers::parse_cookie;
+
+    let jar = CookieJar::new();
+    jar.store("https://example.com/", &parse_cookie(String::from("Set-Cookie: session=abc; SameSite=Strict; HttpOnly; Max-Age=600")).unwrap());
+    jar.store("https://example.com/docs/", &parse_cookie(String::from("Set-Cookie: __Secure-pref=1; Secure; Domain=example.com")).unwrap());
+
+    let mut saved = Vec::new();
+    jar.save_json(&mut saved).unwrap();
+    let document: serde_json::Value = serde_json::from_slice(&saved).unwrap();
+    assert_eq!(document[0]["same_site"], "Strict");
+    assert_eq!(document[1]["expires"], serde_json::Value::Null);
+
+    let loaded = CookieJar::new();
+    assert_eq!(loaded.load_json(&saved[..]).unwrap(), 2);
+    let restored = loaded.cookies();
+    assert_eq!(restored[0].same_site, Some(SameSite::Strict));
+    assert!(restored[0].http_only && restored[0].host_only);
+    assert_eq!((restored[1].domain.as_str(), restored[1].path.as_str(), restored[1].secure), ("example.com", "/docs", true));
+    assert_eq!(loaded.header("https://example.com/docs/"), jar.header("https://example.com/docs/"));
+
+    let expired = r#"[{"name": "old", "value": "1", "domain": "example.com", "expires": 1}, {"name": "new", "value": "2", "domain": "example.com"}]"#;
+    assert_eq!(loaded.load_json(expired.as_bytes()).unwrap(), 1);
+
+    assert!(matches!(loaded.load_json(&b"{\"name\": \"x\"}"[..]), Err(Error::Json { status: None, .. })));
+    assert!(matches!(loaded.load_json(&b"[{\"name\": \"x\", \"value\": 1, \"domain\": \"example.com\"}]"[..]), Err(Error::Json { .. })));
+    assert!(matches!(loaded.load_json(&b"[{"[..]), Err(Error::Json { .. })));
+}
@@ -0,0 +1,113 @@
+use std::io::{Cursor, Read};
+
+use crate::multipart::{Multipart, Part};
+use crate::structs::Request;
+
+use super::{mock_client, sent};
+
+const CREATED: &str = "HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n";
+
+fn encode(form: Multipart) -> String {
+    let mut encoded = String::new();
+    form.into_reader().read_to_string(&mut encoded).unwrap();
+    encoded
+}
+
+#[test]
+fn test_multipart_encoding() {
+    let mut form = Multipart::new();
+    form.set_boundary("XyZ")
+        .add_text("title", "Curio")
+        .add_part("upload", Part::bytes(b"\x00binary\xFF".to_vec()).set_file_name("data.bin").set_header("Content-ID", "<part@curio>"))
+        .add_part("say \"hi\"\r\n", Part::text("a\r\nb").set_file_name("notes\".txt").set_content_type("text/plain; charset=utf-8"));
+
+    let length = form.length();
+    assert_eq!(form.content_type(), "multipart/form-data; boundary=XyZ");
+
+    let mut encoded = Vec::new();
+    form.into_reader().read_to_end(&mut encoded).unwrap();
+    assert_eq!(length, Some(encoded.len() as u64));
+    assert_eq!(encoded, [
+        &b"--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nCurio\r\n"[..],
+        b"--XyZ\r\nContent-Disposition: form-data; name=\"upload\"; filename=\"data.bin\"\r\nContent-Type: application/octet-stream\r\nContent-ID: <part@curio>\r\n\r\n\x00binary\xFF\r\n",
+        b"--XyZ\r\nContent-Disposition: form-data; name=\"say %22hi%22%0D%0A\"; filename=\"notes%22.txt\"\r\nContent-Type: text/plain; charset=utf-8\r\n\r\na\r\nb\r\n",
+        b"--XyZ--\r\n",
+    ].concat());
+}
+
+#[test]
+fn test_multipart_empty_form() {
+    let mut form = Multipart::new();
+    form.set_boundary("empty");
+    assert_eq!(form.length(), Some(11));
+    assert_eq!(encode(form), "--empty--\r\n");
+}
+
+#[test]
+fn test_multipart_header_values_can_not_break_lines() {
+    let mut form = Multipart::new();
+    form.set_boundary("b").add_part("field", Part::text("v").set_content_type("text/plain\r\nX-Injected: 1").set_header("X-Note", "one\ntwo"));
+
+    let encoded = encode(form);
+    assert!(encoded.contains("Content-Type: text/plain  X-Injected: 1\r\n"));
+    assert!(encoded.contains("X-Note: one two\r\n"));
+}
+
+#[test]
+fn test_multipart_generated_boundaries_differ() {
+    let (first, second) = (Multipart::new(), Multipart::new());
+    assert_ne!(first.boundary(), second.boundary());
+    assert!(first.boundary().len() <= 70);
+    assert!(first.boundary().bytes().all(|b| b == b'-' || b.is_ascii_hexdigit()));
+}
+
+#[test]
+fn test_multipart_request_with_length() {
+    let (mut client, mock) = mock_client(&[CREATED]);
+    let mut form = Multipart::new();
+    form.set_boundary("boundary").add_text("a", "1");
+    let length = form.length().unwrap();
+
+    let response = client.send(Request::post("http://example.com/upload").set_multipart(form)).unwrap();
+    assert_eq!(response.status, Some(201));
+
+    let sent = sent(&mock, 0);
+    assert!(sent.contains("Content-Type: multipart/form-data; boundary=boundary\r\n"));
+    assert!(sent.contains(&format!("Content-Length: {}\r\n", length)));
+    assert!(!sent.contains("Transfer-Encoding"));
+    assert!(sent.ends_with("\r\n\r\n--boundary\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n--boundary--\r\n"));
+}
+
+#[test]
+fn test_multipart_request_with_unknown_length_is_chunked() {
+    let (mut client, mock) = mock_client(&[CREATED]);
+    let mut form = Multipart::new();
+    form.set_boundary("b").add_part("log", Part::reader(Cursor::new(b"streamed".to_vec()), None));
+    assert_eq!(form.length(), None);
+
+    client.send(Request::post("http://example.com/upload").set_multipart(form)).unwrap();
+
+    let sent = sent(&mock, 0);
+    assert!(sent.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(!sent.contains("Content-Length"));
+    assert!(sent.ends_with("Content-Type: application/octet-stream\r\n\r\nstreamed\r\n--b--\r\n\r\n0\r\n\r\n"));
+}
+
+#[test]
+fn test_multipart_file_part() {
+    let path = std::env::temp_dir().join(format!("curio-multipart-{}.json", std::process::id()));
+    std::fs::write(&path, "{\"large\": true}").unwrap();
+
+    let mut form = Multipart::new();
+    form.set_boundary("f").add_file("config", &path).unwrap();
+    let length = form.length();
+    let encoded = encode(form);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(length, Some(encoded.len() as u64));
+    let file_name = path.file_name().unwrap().to_string_lossy();
+    assert!(encoded.starts_with(&format!("--f\r\nContent-Disposition: form-data; name=\"config\"; filename=\"{}\"\r\nContent-Type: application/json\r\n\r\n{{\"large\": true}}\r\n", file_name)));
+
+    let missing = Multipart::new().add_file("config", std::env::temp_dir().join("curio-missing-file")).map(|_| ());
+    assert!(matches!(missing, Err(crate::types::Error::Io(_))));
+}
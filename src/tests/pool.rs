@@ -0,0 +1,209 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::pool::{Pool, PoolKey};
+use crate::structs::{ClientConfig, PostData, Request};
+use crate::transport::MockTransport;
+use crate::types::{Error, TimeoutPhase};
+
+use super::mock_client;
+
+const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+
+#[test]
+fn test_pool_reuses_keep_alive_connection() {
+    let (mut client, mock) = mock_client(&[&[OK, OK, OK].concat()]);
+
+    for _ in 0..3 {
+        let response = client.send(&Request::get("https://example.com/items")).unwrap();
+        assert_eq!(response.text(), "ok");
+    }
+
+    assert_eq!(mock.connections().len(), 1);
+    assert_eq!(client.idle_connections(), 1);
+    let sent = String::from_utf8(mock.requests()[0].clone()).unwrap();
+    assert_eq!(sent.matches("GET /items HTTP/1.1\r\n").count(), 3);
+}
+
+#[test]
+fn test_pool_is_per_host() {
+    let (mut client, mock) = mock_client(&[&[OK, OK].concat(), OK, OK]);
+
+    client.send(&Request::get("https://example.com/")).unwrap();
+    client.send(&Request::get("http://example.com/")).unwrap();
+    client.send(&Request::get("https://example.org/")).unwrap();
+    client.send(&Request::get("https://example.com/")).unwrap();
+
+    let connections = mock.connections();
+    assert_eq!(connections.len(), 3);
+    assert_eq!((connections[0].host.as_str(), connections[0].secure), ("example.com", true));
+    assert_eq!((connections[1].host.as_str(), connections[1].secure), ("example.com", false));
+    assert_eq!(connections[2].host, "example.org");
+    assert_eq!(client.idle_connections(), 3);
+}
+
+#[test]
+fn test_pool_honours_connection_close() {
+    let (mut client, mock) = mock_client(&[
+        "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+        "HTTP/1.0 200 OK\r\nContent-Length: 0\r\n\r\n",
+        "HTTP/1.1 200 OK\r\n\r\nread until the server closes",
+        OK,
+    ]);
+
+    for _ in 0..4 {
+        client.send(&Request::get("https://example.com/")).unwrap();
+    }
+
+    assert_eq!(mock.connections().len(), 4);
+    assert_eq!(client.idle_connections(), 1);
+}
+
+#[test]
+fn test_pool_request_connection_close() {
+    let (mut client, mock) = mock_client(&[OK, OK]);
+
+    client.send(Request::get("https://example.com/").set_header("Connection", "close")).unwrap();
+    client.send(&Request::get("https://example.com/")).unwrap();
+
+    assert_eq!(mock.connections().len(), 2);
+}
+
+#[test]
+fn test_pool_retries_stale_connection() {
+    let (mut client, mock) = mock_client(&[OK, OK]);
+
+    client.send(&Request::get("https://example.com/")).unwrap();
+    // the first script is exhausted, as if the server closed the idle connection
+    let response = client.send(&Request::get("https://example.com/")).unwrap();
+
+    assert_eq!(response.status.unwrap(), 200);
+    assert_eq!(mock.connections().len(), 2);
+}
+
+#[test]
+fn test_pool_does_not_retry_non_idempotent_request() {
+    let (mut client, mock) = mock_client(&[OK, OK]);
+
+    client.send(&Request::get("https://example.com/")).unwrap();
+    let response = client.send(Request::post("https://example.com/").set_body(&PostData::from_str("once")));
+
+    assert!(response.is_err());
+    assert_eq!(mock.connections().len(), 1);
+    assert_eq!(client.idle_connections(), 0);
+}
+
+#[test]
+fn test_pool_evicts_idle_connections() {
+    let (mut client, mock) = mock_client(&[&[OK, OK].concat(), OK]);
+    client.config.idle_timeout = Duration::from_secs(0);
+
+    client.send(&Request::get("https://example.com/")).unwrap();
+    client.send(&Request::get("https://example.com/")).unwrap();
+
+    assert_eq!(mock.connections().len(), 2);
+}
+
+#[test]
+fn test_pool_disabled() {
+    let (mut client, mock) = mock_client(&[OK, OK, OK, OK]);
+
+    client.config.cycle_connections = true;
+    client.send(&Request::get("https://example.com/")).unwrap();
+    client.send(&Request::get("https://example.com/")).unwrap();
+
+    client.config.cycle_connections = false;
+    client.config.connection_limit = 0;
+    client.send(&Request::get("https://example.com/")).unwrap();
+    client.send(&Request::get("https://example.com/")).unwrap();
+
+    assert_eq!(mock.connections().len(), 4);
+    assert_eq!(client.idle_connections(), 0);
+}
+
+#[test]
+fn test_pool_close_idle_connections() {
+    let (mut client, mock) = mock_client(&[&[OK, OK].concat(), OK]);
+
+    client.send(&Request::get("https://example.com/")).unwrap();
+    client.close_idle_connections();
+    assert_eq!(client.idle_connections(), 0);
+
+    client.send(&Request::get("https://example.com/")).unwrap();
+    assert_eq!(mock.connections().len(), 2);
+}
+
+#[test]
+fn test_pool_connection_limit() {
+    let mock = MockTransport::new();
+    for _ in 0..3 {
+        mock.push_response(OK);
+    }
+
+    let pool = Pool::new();
+    let config = ClientConfig { connection_limit: 2, ..ClientConfig::default() };
+    let key = PoolKey::new("example.com", 443, true);
+
+    let connections: Vec<_> = (0..2).map(|_| pool.checkout(&mock, &key, &config, None).unwrap()).collect();
+    let waited = pool.checkout(&mock, &key, &config, Some(Duration::from_millis(50)));
+    assert!(matches!(waited, Err(Error::Timeout { phase: TimeoutPhase::Connect, .. })));
+    assert_eq!(mock.connections().len(), 2);
+
+    // other hosts have limits of their own
+    pool.checkout(&mock, &PoolKey::new("example.org", 443, true), &config, None).unwrap();
+
+    for connection in connections {
+        pool.checkin(connection, true, &config);
+    }
+    assert_eq!(pool.idle_count(), 2);
+
+    pool.checkout(&mock, &key, &config, None).unwrap();
+    assert_eq!(pool.idle_count(), 1);
+    assert_eq!(mock.connections().len(), 3);
+}
+
+#[test]
+fn test_pool_waits_for_a_free_connection() {
+    let mock = MockTransport::new();
+    mock.push_response(OK);
+
+    let pool = Pool::new();
+    let config = ClientConfig { connection_limit: 1, ..ClientConfig::default() };
+    let key = PoolKey::new("example.com", 443, true);
+
+    let connection = pool.checkout(&mock, &key, &config, None).unwrap();
+    let returned = {
+        let (pool, config) = (pool.clone(), config.clone());
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            pool.checkin(connection, true, &config);
+        })
+    };
+
+    let connection = pool.checkout(&mock, &key, &config, Some(Duration::from_secs(5))).unwrap();
+    returned.join().unwrap();
+    assert_eq!(connection.domain, "example.com");
+    assert_eq!(mock.connections().len(), 1);
+
+    // closing a connection frees its place as well
+    let waiting = {
+        let (pool, mock, key, config) = (pool.clone(), mock.clone(), key.clone(), config.clone());
+        mock.push_response(OK);
+        thread::spawn(move || pool.checkout(&mock, &key, &config, None).map(|_| ()))
+    };
+    thread::sleep(Duration::from_millis(50));
+    pool.discard(connection);
+    waiting.join().unwrap().unwrap();
+    assert_eq!(mock.connections().len(), 2);
+}
+
+#[test]
+fn test_pool_host_is_case_insensitive() {
+    let (mut client, mock) = mock_client(&[&[OK, OK].concat()]);
+
+    client.send(&Request::get("https://Example.COM/")).unwrap();
+    client.send(&Request::get("https://example.com/")).unwrap();
+
+    assert_eq!(mock.connections().len(), 1);
+    assert_eq!(mock.connections()[0].host, "example.com");
+}
@@ -0,0 +1,178 @@
+use crate::structs::{PostData, Request};
+use crate::types::Error;
+use crate::utils::parsers::resolve_url;
+
+use super::{mock_client, sent};
+
+fn redirect(status: &str, location: &str) -> String {
+    format!("HTTP/1.1 {}\r\nLocation: {}\r\nContent-Length: 0\r\n\r\n", status, location)
+}
+
+#[test]
+fn test_resolve_url_reference_examples() {
+    // the normal examples from RFC 3986 section 5.4.1, with an http base
+    let base = "http://a/b/c/d;p?q";
+    let examples = [
+        ("g", "http://a/b/c/g"),
+        ("./g", "http://a/b/c/g"),
+        ("g/", "http://a/b/c/g/"),
+        ("/g", "http://a/g"),
+        ("//g", "http://g"),
+        ("?y", "http://a/b/c/d;p?y"),
+        ("g?y", "http://a/b/c/g?y"),
+        ("#s", "http://a/b/c/d;p?q"),
+        ("g#s", "http://a/b/c/g"),
+        ("", "http://a/b/c/d;p?q"),
+        (".", "http://a/b/c/"),
+        ("./", "http://a/b/c/"),
+        ("..", "http://a/b/"),
+        ("../", "http://a/b/"),
+        ("../g", "http://a/b/g"),
+        ("../..", "http://a/"),
+        ("../../g", "http://a/g"),
+        ("../../../g", "http://a/g"),
+        ("/./g", "http://a/g"),
+        ("g;x=1/../y", "http://a/b/c/y"),
+        ("https://other.example/path", "https://other.example/path"),
+    ];
+
+    for (reference, expected) in examples.iter() {
+        assert_eq!(resolve_url(base, reference).unwrap(), *expected, "resolving {:?}", reference);
+    }
+}
+
+#[test]
+fn test_redirect_follows_relative_location() {
+    let (mut client, mock) = mock_client(&[&[redirect("302 Found", "../moved?page=2").as_str(), "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok"].concat()]);
+
+    let response = client.send(&Request::get("https://example.com/a/b/old")).unwrap();
+
+    assert_eq!(response.text(), "ok");
+    assert_eq!(response.redirects.len(), 1);
+    assert_eq!(response.redirects[0].status, 302);
+    assert_eq!(response.redirects[0].from, "https://example.com/a/b/old");
+    assert_eq!(response.redirects[0].to, "https://example.com/a/moved?page=2");
+    assert!(sent(&mock, 0).contains("\r\n\r\nGET /a/moved?page=2 HTTP/1.1\r\n"));
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[test]
+fn test_redirect_location_is_percent_encoded() {
+    assert_eq!(resolve_url("http://a/b", "/caf\u{e9} menu?q=a b#top").unwrap(), "http://a/caf%C3%A9%20menu?q=a%20b");
+    assert_eq!(resolve_url("http://a/b", "/already%20encoded").unwrap(), "http://a/already%20encoded");
+
+    let (mut client, mock) = mock_client(&[&[redirect("302 Found", "/a b").as_str(), "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok"].concat()]);
+
+    let response = client.send(&Request::get("https://example.com/start")).unwrap();
+    assert_eq!(response.text(), "ok");
+    assert_eq!(response.redirects[0].to, "https://example.com/a%20b");
+    assert!(sent(&mock, 0).contains("\r\n\r\nGET /a%20b HTTP/1.1\r\n"));
+}
+
+#[test]
+fn test_redirect_303_becomes_get() {
+    let (mut client, mock) = mock_client(&[&[redirect("303 See Other", "/result").as_str(), "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"].concat()]);
+
+    let mut request = Request::post("https://example.com/submit");
+    request.set_body(&PostData::from_str("form data")).set_header("Content-Encoding", "identity");
+    client.send(&request).unwrap();
+
+    let sent = sent(&mock, 0);
+    let second = &sent[sent.find("GET /result").unwrap()..];
+    assert!(!second.contains("Content-Length"));
+    assert!(!second.contains("Content-Encoding"));
+    assert!(!second.contains("form data"));
+}
+
+#[test]
+fn test_redirect_307_308_keep_method_and_body() {
+    for status in ["307 Temporary Redirect", "308 Permanent Redirect"].iter() {
+        let (mut client, mock) = mock_client(&[&[redirect(status, "/v2/items").as_str(), "HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n"].concat()]);
+
+        let response = client.send(Request::put("https://example.com/v1/items").set_body(&PostData::from_str("item"))).unwrap();
+        assert_eq!(response.status, Some(201));
+
+        let sent = sent(&mock, 0);
+        let second = &sent[sent.find("PUT /v2/items HTTP/1.1\r\n").unwrap()..];
+        assert!(second.ends_with("\r\n\r\nitem"));
+    }
+}
+
+#[test]
+fn test_redirect_301_post_becomes_get() {
+    let (mut client, mock) = mock_client(&[&[redirect("301 Moved Permanently", "/new").as_str(), "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"].concat()]);
+
+    client.send(Request::post("https://example.com/old").set_body(&PostData::from_str("data"))).unwrap();
+    let sent = sent(&mock, 0);
+    let second = &sent[sent.find("GET /new HTTP/1.1\r\n").unwrap()..];
+    assert!(second.starts_with("GET /new HTTP/1.1\r\nUser-Agent: Curio/0.1.0\r\nHost: example.com\r\nConnection: Keep-Alive\r\n"));
+    assert!(second.ends_with("\r\n\r\n"));
+    assert!(!second.contains("Content-"));
+}
+
+#[test]
+fn test_redirect_strips_credentials_across_origins() {
+    let (mut client, mock) = mock_client(&[
+        &[redirect("302 Found", "/same-origin").as_str(), redirect("302 Found", "https://other.example/landing").as_str()].concat(),
+        "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+    ]);
+
+    let mut request = Request::get("https://example.com/start");
+    request.set_header("Authorization", "Bearer secret").set_header("cookie", "session=1").set_header("X-Trace", "kept");
+    let response = client.send(&request).unwrap();
+
+    assert_eq!(response.redirects.len(), 2);
+    let first = sent(&mock, 0);
+    assert_eq!(first.matches("Authorization: Bearer secret").count(), 2);
+    assert_eq!(first.matches("cookie: session=1").count(), 2);
+
+    let other = sent(&mock, 1);
+    assert_eq!(mock.connections()[1].host, "other.example");
+    assert!(!other.contains("Authorization"));
+    assert!(!other.contains("cookie"));
+    assert!(other.contains("X-Trace: kept"));
+}
+
+#[test]
+fn test_redirect_limit() {
+    let loop_script = [redirect("302 Found", "/a").as_str(), redirect("302 Found", "/b").as_str(), redirect("302 Found", "/c").as_str()].concat();
+    let (mut client, _) = mock_client(&[&loop_script]);
+    client.config.redirect_limit = 2;
+
+    let error = client.send(&Request::get("https://example.com/")).unwrap_err();
+    assert!(matches!(error, Error::TooManyRedirects { limit: 2 }));
+
+    let (mut client, _) = mock_client(&[&loop_script]);
+    client.config.redirect_limit = 0;
+
+    let response = client.send(&Request::get("https://example.com/")).unwrap();
+    assert_eq!(response.status, Some(302));
+    assert!(response.redirects.is_empty());
+}
+
+#[test]
+fn test_redirect_without_location_is_returned() {
+    let (mut client, _) = mock_client(&["HTTP/1.1 302 Found\r\nContent-Length: 0\r\n\r\n"]);
+    assert_eq!(client.send(&Request::get("https://example.com/")).unwrap().status, Some(302));
+}
+
+#[test]
+fn test_redirect_to_custom_scheme_is_returned() {
+    let (mut client, mock) = mock_client(&[&redirect("302 Found", "myapp://callback?code=abc")]);
+
+    let response = client.send(&Request::get("https://example.com/authorize")).unwrap();
+    assert_eq!(response.status, Some(302));
+    assert_eq!(response.headers.get("Location").unwrap(), "myapp://callback?code=abc");
+    assert!(response.redirects.is_empty());
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[test]
+fn test_redirect_upgrade_can_be_refused() {
+    let (mut client, mock) = mock_client(&[&redirect("301 Moved Permanently", "https://example.com/")]);
+    client.config.auto_upgrade = false;
+
+    let response = client.send(&Request::get("http://example.com/")).unwrap();
+    assert_eq!(response.status, Some(301));
+    assert_eq!(mock.connections().len(), 1);
+}
@@ -8,21 +8,163 @@ fn test_request_builder() {
 
 #[test]
 fn test_request_builder_get() {
-    let response = crate::structs::Request::get("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md").send().unwrap();
+    let mock = crate::transport::MockTransport::new();
+    mock.push_response(concat!("HTTP/1.1 204 No Content\r\nAllow: GET, OPTIONS\r\n\r\n", "HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\n# Curio"));
+    let response = crate::structs::Request::get("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md").send_with(&mock).unwrap();
     println!("{:#?}", response);
     assert_eq!(response.status.unwrap(), 200);
+    assert_eq!(mock.connections().len(), 1);
+    assert_eq!(mock.requests()[0].windows(4).filter(|window| window == b"HTTP").count(), 2);
 }
 
 #[test]
 fn test_request_builder_tls_post_pastebin() {
-    let response = crate::structs::Request::post("https://paste.mod.gg/documents").set_body(&crate::structs::PostData::from_str("This is a PasteBin document, posted and created by Curio version 0.0.2")).send().unwrap();
+    let mock = crate::transport::MockTransport::new();
+    mock.push_response(concat!("HTTP/1.1 204 No Content\r\nAllow: POST, OPTIONS\r\n\r\n", "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 15\r\n\r\n{\"key\":\"curio\"}"));
+    let response = crate::structs::Request::post("https://paste.mod.gg/documents").set_body(&crate::structs::PostData::from_str("This is a PasteBin document, posted and created by Curio version 0.0.2")).send_with(&mock).unwrap();
     println!("{:#?}", response);
     assert_eq!(response.status.unwrap(), 200);
+    let sent = String::from_utf8(mock.requests()[0].clone()).unwrap();
+    assert!(sent.starts_with("OPTIONS /documents HTTP/1.1\r\n"));
+    assert!(sent.contains("\r\n\r\nPOST /documents HTTP/1.1\r\n"));
+    assert!(sent.ends_with("\r\n\r\nThis is a PasteBin document, posted and created by Curio version 0.0.2"));
 }
 
 #[test]
 fn test_request_builder_tcp_post_pastebin() {
-    let response = crate::structs::Request::post("http://paste.mod.gg/documents").set_body(&crate::structs::PostData::from_str("This is a PasteBin document, posted and created by Curio version 0.0.2")).send().unwrap();
+    let mock = crate::transport::MockTransport::new();
+    mock.push_response(concat!("HTTP/1.1 204 No Content\r\nAllow: POST, OPTIONS\r\n\r\n", "HTTP/1.1 308 Permanent Redirect\r\nLocation: https://paste.mod.gg/documents\r\nContent-Length: 0\r\n\r\n"));
+    mock.push_response("HTTP/1.1 200 OK\r\nContent-Length: 15\r\n\r\n{\"key\":\"curio\"}");
+    let response = crate::structs::Request::post("http://paste.mod.gg/documents").set_body(&crate::structs::PostData::from_str("This is a PasteBin document, posted and created by Curio version 0.0.2")).send_with(&mock).unwrap();
     println!("{:#?}", response);
-    assert_eq!(response.status.unwrap(), 308);
-}
\ No newline at end of file
+    assert_eq!(response.status.unwrap(), 200);
+    assert_eq!(response.redirects.len(), 1);
+    assert_eq!(response.redirects[0].status, 308);
+    assert!(!mock.connections()[0].secure);
+    assert!(mock.connections()[1].secure);
+    let sent = String::from_utf8(mock.requests()[1].clone()).unwrap();
+    assert!(sent.starts_with("POST /documents HTTP/1.1\r\n"));
+    assert!(sent.ends_with("\r\n\r\nThis is a PasteBin document, posted and created by Curio version 0.0.2"));
+}
+
+#[test]
+fn test_request_builder_put_patch_body() {
+    let body = crate::structs::PostData::from_str("replacement content");
+    let mut put = crate::structs::Request::put("http://localhost/documents/1");
+    put.set_body(&body);
+    assert!(matches!(put.request_type, crate::structs::RequestType::Put));
+    assert_eq!(crate::utils::body_headers(&put, &crate::codec::OutgoingBody::new(&put).unwrap()), vec![("Content-Length", "19".to_string()), ("Content-Type", "text/plain; charset=utf-8".to_string())]);
+
+    let patch = crate::structs::Request::patch("http://localhost/documents/1");
+    assert!(matches!(patch.request_type, crate::structs::RequestType::Patch));
+    assert_eq!(crate::utils::body_headers(&patch, &crate::codec::OutgoingBody::new(&patch).unwrap()), vec![("Content-Length", "0".to_string())]);
+}
+
+#[test]
+fn test_request_builder_custom_method() {
+    use crate::structs::{Request, RequestType};
+
+    let mut propfind = Request::custom("PROPFIND", "http://localhost/calendars/");
+    assert_eq!(propfind.request_type.as_str(), "PROPFIND");
+    assert!(propfind.request_type.is_safe());
+    assert!(propfind.request_type.is_idempotent());
+    assert!(crate::utils::body_headers(&propfind, &crate::codec::OutgoingBody::new(&propfind).unwrap()).is_empty());
+    propfind.set_body(&crate::structs::PostData::from_str("<propfind/>"));
+    assert_eq!(crate::utils::body_headers(&propfind, &crate::codec::OutgoingBody::new(&propfind).unwrap()), vec![("Content-Length", "11".to_string()), ("Content-Type", "text/plain; charset=utf-8".to_string())]);
+
+    assert!(matches!(Request::custom("GET", "http://localhost/").request_type, RequestType::Get));
+    assert!(RequestType::from_method("MKCOL").unwrap().is_idempotent());
+    assert!(!RequestType::from_method("LOCK").unwrap().is_idempotent());
+    assert!(!RequestType::from_method("PURGE").unwrap().is_safe());
+    assert!(RequestType::from_method("BAD METHOD").is_err());
+    assert!(RequestType::from_method("").is_err());
+    assert!(RequestType::from_method("CONNECT").is_err());
+}
+
+#[test]
+fn test_request_builder_custom_method_rejected_before_sending() {
+    let mock = crate::transport::MockTransport::new();
+    mock.push_response("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+
+    let result = crate::structs::Request::custom("GET\r\nHost:", "http://localhost/").send_with(&mock);
+    assert!(matches!(result, Err(crate::types::Error::InvalidMethod(method)) if method == "GET\r\nHost:"));
+
+    let mut trace = crate::structs::Request::custom("TRACE", "http://localhost/");
+    trace.set_body(&crate::structs::PostData::from_str("not allowed"));
+    assert!(matches!(trace.send_with(&mock), Err(crate::types::Error::InvalidMethod(method)) if method == "TRACE"));
+
+    assert!(mock.connections().is_empty());
+    assert!(mock.requests().is_empty());
+}
+
+#[test]
+fn test_request_builder_headers_override_defaults() {
+    let mut request = crate::structs::Request::put("http://localhost:8080/documents/1");
+    request.set_header("user-agent", "integration-tests/1.0");
+    request.set_header("Authorization", "Bearer token");
+    request.set_header("Content-Length", "9000");
+    request.set_body(&crate::structs::PostData::from_str("content"));
+
+    let mut defaults = crate::utils::default_headers(crate::utils::host_header(&request.domain, request.port, 80));
+    defaults.append(&mut crate::utils::body_headers(&request, &crate::codec::OutgoingBody::new(&request).unwrap()));
+    let block = crate::utils::build_headers(defaults, &request.headers);
+
+    assert_eq!(block, "Host: localhost:8080\r\nConnection: Keep-Alive\r\nContent-Length: 7\r\nContent-Type: text/plain; charset=utf-8\r\nuser-agent: integration-tests/1.0\r\nAuthorization: Bearer token\r\n\r\n");
+}
+
+#[test]
+fn test_request_builder_explicit_content_types() {
+    let body_of = |data: &crate::structs::PostData| {
+        let bytes = crate::codec::serialize_request(crate::structs::Request::post("http://localhost/").set_body(data), 80).unwrap();
+        let start = bytes.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+        (String::from_utf8_lossy(&bytes[..start + 2]).into_owned(), bytes[start + 4..].to_vec())
+    };
+
+    let (head, body) = body_of(&crate::structs::PostData::with_content_type("{\"id\": 1}", "application/json").unwrap());
+    assert!(head.contains("Content-Type: application/json; charset=utf-8\r\n"));
+    assert_eq!(body, b"{\"id\": 1}");
+
+    let (head, body) = body_of(&crate::structs::PostData::from_bytes(vec![0x00, 0xFF, 0x10], "application/octet-stream"));
+    assert!(head.contains("Content-Type: application/octet-stream\r\n"));
+    assert!(head.contains("Content-Length: 3\r\n"));
+    assert_eq!(body, vec![0x00, 0xFF, 0x10]);
+
+    let mut latin = crate::structs::PostData::text("café €");
+    latin.set_charset("ISO-8859-1").unwrap();
+    let (head, body) = body_of(&latin);
+    assert!(head.contains("Content-Type: text/plain; charset=ISO-8859-1\r\n"));
+    assert_eq!(body, b"caf\xE9 ?");
+
+    let mut form = crate::structs::PostData::from_tuple(vec!(("q", "€")));
+    form.set_charset("windows-1252").unwrap();
+    let (head, body) = body_of(&form);
+    assert!(head.contains("Content-Type: application/x-www-form-urlencoded; charset=windows-1252\r\n"));
+    assert_eq!(body, b"q=%80");
+
+    let mut xml = crate::structs::PostData::text("<a/>");
+    xml.set_content_type("application/xml; version=1; charset=\"UTF-16LE\"").unwrap();
+    assert_eq!(xml.content_type, "application/xml; version=1");
+    assert_eq!(xml.deserialize().unwrap(), (String::from("application/xml; version=1; charset=utf-16le"), vec![b'<', 0, b'a', 0, b'/', 0, b'>', 0]));
+
+    assert!(matches!(crate::structs::PostData::text("x").set_charset("koi8-r"), Err(crate::types::Error::UnsupportedCharset(_))));
+
+    let mut form = crate::structs::PostData::text("x");
+    assert!(matches!(form.set_content_type("multipart/form-data; charset=shift_jis"), Err(crate::types::Error::UnsupportedCharset(charset)) if charset == "shift_jis"));
+    assert_eq!(form.content_type_header(), "text/plain; charset=utf-8");
+    assert!(matches!(crate::structs::PostData::with_content_type("x", "text/csv; charset=shift_jis"), Err(crate::types::Error::UnsupportedCharset(_))));
+
+    // a charset assigned to the field directly is only found when the body is encoded
+    let mut direct = crate::structs::PostData::text("x");
+    direct.charset = Some(String::from("shift_jis"));
+    assert!(matches!(direct.deserialize(), Err(crate::types::Error::UnsupportedCharset(charset)) if charset == "shift_jis"));
+
+    let mock = crate::transport::MockTransport::new();
+    mock.push_response("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+    let result = crate::structs::Request::post("http://localhost/").set_body(&direct).send_with(&mock);
+    assert!(matches!(result, Err(crate::types::Error::UnsupportedCharset(_))));
+    assert!(mock.requests().is_empty());
+
+    let mut form = crate::structs::PostData::from_tuple(vec!(("a", "b")));
+    form.charset = Some(String::from("koi8-r"));
+    assert!(matches!(form.deserialize(), Err(crate::types::Error::UnsupportedCharset(_))));
+}
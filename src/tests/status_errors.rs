@@ -0,0 +1,48 @@
+use crate::structs::{Request, Response};
+use crate::types::Error;
+
+use super::mock_client;
+
+fn response(status_line: &str) -> Response {
+    Response::new(String::new(), vec![status_line.to_string()]).unwrap()
+}
+
+#[test]
+fn test_error_for_status_passes_success() {
+    for status_line in ["HTTP/1.1 200 OK", "HTTP/1.1 204 No Content", "HTTP/1.1 304 Not Modified"].iter() {
+        assert!(response(status_line).error_for_status().is_ok());
+    }
+}
+
+#[test]
+fn test_error_for_status_typed_variants() {
+    assert!(matches!(response("HTTP/1.1 404 Not Found").error_for_status(), Err(Error::E404NotFound(_))));
+    assert!(matches!(response("HTTP/1.1 429 Too Many Requests").error_for_status(), Err(Error::E429TooManyRequests(_))));
+    assert!(matches!(response("HTTP/1.1 503 Service Unavailable").error_for_status(), Err(Error::E503ServiceUnavailable(_))));
+    assert!(matches!(response("HTTP/1.1 499 Client Closed Request").error_for_status(), Err(Error::E4XXClientError(_))));
+    assert!(matches!(response("HTTP/1.1 599 Network Timeout").error_for_status(), Err(Error::E5XXServerError(_))));
+
+    let error = response("HTTP/1.1 599 Network Timeout").error_for_status().unwrap_err();
+    assert_eq!(error.to_string(), "HTTP 599 - Network Timeout");
+    assert_eq!(error.status(), Some(599));
+}
+
+#[test]
+fn test_error_for_status_keeps_response() {
+    let (mut client, _) = mock_client(&["HTTP/1.1 429 Too Many Requests\r\nRetry-After: 30\r\nContent-Length: 9\r\n\r\nslow down"]);
+    client.config.error_for_status = true;
+
+    let error = client.send(&Request::get("https://example.com/")).unwrap_err();
+    assert_eq!(error.status(), Some(429));
+    assert_eq!(error.response().unwrap().headers.get("Retry-After").unwrap(), "30");
+    assert_eq!(error.into_response().unwrap().text(), "slow down");
+}
+
+#[test]
+fn test_error_for_status_is_opt_in() {
+    let (mut client, _) = mock_client(&["HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n"]);
+
+    let response = client.send(&Request::get("https://example.com/")).unwrap();
+    assert_eq!(response.status, Some(500));
+    assert!(!response.is_success());
+}
@@ -0,0 +1,116 @@
+use std::io::Read;
+
+use crate::structs::Request;
+use crate::types::Error;
+
+use super::mock_client;
+
+const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+
+#[test]
+fn test_stream_content_length_body() {
+    let (mut client, mock) = mock_client(&[&["HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world", OK].concat()]);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/file")).unwrap();
+    assert_eq!(streaming.response.status, Some(200));
+    assert_eq!(streaming.response.body, None);
+
+    let mut first = [0; 5];
+    streaming.body.read_exact(&mut first).unwrap();
+    assert_eq!(&first, b"hello");
+    assert!(!streaming.body.is_finished());
+    assert_eq!(client.idle_connections(), 0);
+
+    let mut rest = Vec::new();
+    streaming.body.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b" world");
+    assert!(streaming.body.is_finished());
+    assert_eq!(client.idle_connections(), 1);
+
+    let response = client.send(&Request::get("http://example.com/next")).unwrap();
+    assert_eq!(response.text(), "ok");
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[test]
+fn test_stream_chunked_body() {
+    let (mut client, mock) = mock_client(&[&["HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n", OK].concat()]);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/wiki")).unwrap();
+    let mut body = String::new();
+    streaming.read_to_string(&mut body).unwrap();
+    assert_eq!(body, "Wikipedia");
+    assert_eq!(client.idle_connections(), 1);
+
+    assert_eq!(client.send(&Request::get("http://example.com/next")).unwrap().text(), "ok");
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[test]
+fn test_dropping_unfinished_body_closes_connection() {
+    let (mut client, mock) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world", OK]);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/file")).unwrap();
+    let mut first = [0; 5];
+    streaming.body.read_exact(&mut first).unwrap();
+    drop(streaming);
+
+    assert_eq!(client.idle_connections(), 0);
+    assert_eq!(client.send(&Request::get("http://example.com/next")).unwrap().text(), "ok");
+    assert_eq!(mock.connections().len(), 2);
+}
+
+#[test]
+fn test_empty_body_releases_connection_immediately() {
+    let (mut client, _) = mock_client(&["HTTP/1.1 204 No Content\r\n\r\n"]);
+
+    let streaming = client.send_streaming(&Request::get("http://example.com/")).unwrap();
+    assert!(streaming.body.is_finished());
+    assert_eq!(client.idle_connections(), 1);
+}
+
+#[test]
+fn test_stream_truncated_body() {
+    let (mut client, _) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: 50\r\n\r\nhello"]);
+
+    let streaming = client.send_streaming(&Request::get("http://example.com/")).unwrap();
+    assert!(matches!(streaming.into_response(), Err(Error::MalformedResponse { .. })));
+    assert_eq!(client.idle_connections(), 0);
+}
+
+#[test]
+fn test_stream_body_too_large() {
+    let (mut client, _) = mock_client(&["HTTP/1.1 200 OK\r\n\r\nthis body is far too long"]);
+    client.config.max_body_size = Some(8);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/")).unwrap();
+    let mut body = Vec::new();
+    let error = streaming.body.read_to_end(&mut body).unwrap_err();
+    assert!(matches!(crate::codec::body_error(error), Error::BodyTooLarge { limit: 8 }));
+}
+
+#[test]
+fn test_stream_after_redirect() {
+    let (mut client, mock) = mock_client(&[&["HTTP/1.1 302 Found\r\nLocation: /final\r\nContent-Length: 5\r\n\r\nmoved", "HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\ndone"].concat()]);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/start")).unwrap();
+    assert_eq!(streaming.response.redirects.len(), 1);
+
+    let mut body = String::new();
+    streaming.read_to_string(&mut body).unwrap();
+    assert_eq!(body, "done");
+    assert_eq!(mock.connections().len(), 1);
+}
+
+#[test]
+fn test_stream_chunked_trailers() {
+    let (mut client, _) = mock_client(&["HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n0\r\nX-Checksum: 42\r\n\r\n"]);
+
+    let mut streaming = client.send_streaming(&Request::get("http://example.com/")).unwrap();
+    assert!(streaming.body.trailers().is_empty());
+
+    let mut body = Vec::new();
+    streaming.body.read_to_end(&mut body).unwrap();
+    assert_eq!(streaming.body.trailers().get("X-Checksum").unwrap(), "42");
+    assert_eq!(streaming.into_response().unwrap().trailers.get("X-Checksum").unwrap(), "42");
+}
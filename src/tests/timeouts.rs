@@ -0,0 +1,109 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::structs::{Client, PostData, Request, Timeouts};
+use crate::types::{Error, TimeoutPhase};
+
+/// Starts a server which accepts one connection, reads the request and then runs `respond`
+fn serve<F: FnOnce(std::net::TcpStream) + Send + 'static>(respond: F) -> (u16, thread::JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0u8; 1024];
+        let _ = stream.read(&mut request);
+        respond(stream);
+    });
+
+    (port, handle)
+}
+
+fn client() -> Client {
+    let mut client = Client::new();
+    client.config.perform_preflight = false;
+    client
+}
+
+#[test]
+fn test_read_timeout() {
+    let (port, server) = serve(|_| thread::sleep(Duration::from_millis(1500)));
+
+    let started = Instant::now();
+    let error = client().send(Request::get(format!("http://127.0.0.1:{}/", port)).set_read_timeout(Duration::from_millis(100))).unwrap_err();
+
+    println!("{}", error);
+    assert!(matches!(error, Error::Timeout { phase: TimeoutPhase::Read, .. }));
+    assert!(started.elapsed() < Duration::from_millis(1000));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_total_timeout_covers_body_download() {
+    let (port, server) = serve(|mut stream| {
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n");
+        for _ in 0..30 {
+            thread::sleep(Duration::from_millis(50));
+            if stream.write_all(b"a").is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut client = client();
+    client.config.timeouts.read = Some(Duration::from_secs(1));
+
+    let started = Instant::now();
+    let error = client.send(Request::get(format!("http://127.0.0.1:{}/", port)).set_timeout(Duration::from_millis(300))).unwrap_err();
+
+    println!("{}", error);
+    assert!(matches!(error, Error::Timeout { phase: TimeoutPhase::Total, .. }));
+    assert!(started.elapsed() < Duration::from_millis(1000));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_write_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        // accept the connection but never read from it, so the socket buffers fill up
+        let (_stream, _) = listener.accept().unwrap();
+        thread::sleep(Duration::from_millis(1500));
+    });
+
+    let body = PostData::from_str("a".repeat(64 * 1024 * 1024));
+    let error = client().send(Request::post(format!("http://127.0.0.1:{}/", port)).set_body(&body).set_write_timeout(Duration::from_millis(100))).unwrap_err();
+
+    println!("{}", error);
+    assert!(matches!(error, Error::Timeout { phase: TimeoutPhase::Write, .. }));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_expired_total_timeout_before_connecting() {
+    let error = client().send(Request::get("http://127.0.0.1:9/").set_timeout(Duration::from_nanos(1))).unwrap_err();
+    assert!(matches!(error, Error::Timeout { phase: TimeoutPhase::Total, .. }));
+}
+
+#[test]
+fn test_connect_timeout_phase() {
+    let error = crate::tcp::connect_error("example.com", 80, std::io::Error::from(std::io::ErrorKind::TimedOut));
+    assert!(matches!(error, Error::Timeout { phase: TimeoutPhase::Connect, .. }));
+    assert_eq!(error.to_string(), "Timeout - the connect timeout elapsed: timed out");
+}
+
+#[test]
+fn test_request_timeouts_override_config() {
+    let defaults = Timeouts { connect: Some(Duration::from_secs(5)), read: Some(Duration::from_secs(30)), ..Timeouts::default() };
+    let mut request = Request::get("http://example.com/");
+    request.set_read_timeout(Duration::from_secs(1));
+
+    let timeouts = request.timeouts.or(&defaults);
+    assert_eq!(timeouts.connect, Some(Duration::from_secs(5)));
+    assert_eq!(timeouts.read, Some(Duration::from_secs(1)));
+    assert_eq!(timeouts.write, None);
+    assert_eq!(timeouts.total, None);
+}
@@ -1,53 +1,106 @@
+use crate::structs::{PostData, Request};
+
+use super::{mock_client, sent};
+
 #[test]
 fn test_tls_options() {
-    let request = crate::structs::Request::options("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md");
-    let response = request.send().unwrap();
+    let (mut client, mock) = mock_client(&["HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n"]);
+    let response = client.send(&Request::options("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md")).unwrap();
     println!("{:#?}", response);
     assert_eq!(response.status.unwrap(), 403);
+    assert!(sent(&mock, 0).starts_with("OPTIONS /fatalcenturion/Curio/master/README.md HTTP/1.1\r\n"));
+    assert_eq!(mock.connections()[0].port, 443);
+    assert!(mock.connections()[0].secure);
 }
 
 #[test]
 fn test_tls_delete() {
-    let request = crate::structs::Request::delete("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md");
-    let response = request.send().unwrap();
+    let (mut client, mock) = mock_client(&["HTTP/1.1 403 Forbidden\r\nContent-Length: 9\r\n\r\nForbidden"]);
+    let response = client.send(&Request::delete("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md")).unwrap();
     println!("{:#?}", response);
     assert_eq!(response.status.unwrap(), 403);
+    assert_eq!(response.text(), "Forbidden");
+    assert!(sent(&mock, 0).starts_with("DELETE /fatalcenturion/Curio/master/README.md HTTP/1.1\r\n"));
+    assert!(!sent(&mock, 0).contains("Content-Length"));
 }
 
 #[test]
 fn test_tls_head() {
-    let response = crate::structs::Request::head("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md").send().unwrap();
+    let (mut client, mock) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: 27520\r\n\r\n"]);
+    let response = client.send(&Request::head("https://jsonplaceholder.typicode.com/todos/")).unwrap();
     println!("{:#?}", response);
     assert_eq!(response.status.unwrap(), 200);
+    assert_eq!(response.body, None);
+    assert!(sent(&mock, 0).starts_with("HEAD /todos/ HTTP/1.1\r\nUser-Agent: Curio/0.1.0\r\nHost: jsonplaceholder.typicode.com\r\n"));
 }
 
 #[test]
-fn test_tls_chunked_get() {
-    let response = crate::tls::get("jsonplaceholder.typicode.com", "/todos/", true).unwrap();
+fn test_tls_get() {
+    let (mut client, mock) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 7\r\n\r\n# Curio"]);
+    let response = client.send(&Request::get("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md")).unwrap();
     println!("{:#?}", response);
     assert_eq!(response.status.unwrap(), 200);
+    assert_eq!(response.text(), "# Curio");
+    assert_eq!(mock.connections()[0].host, "raw.githubusercontent.com");
 }
 
 #[test]
-fn test_tls_get() {
-    let response = crate::structs::Request::get("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md").send().unwrap();
+fn test_tls_chunked_get() {
+    let (mut client, _) = mock_client(&["HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n[{\r\n2\r\n}]\r\n0\r\n\r\n"]);
+    let response = client.send(&Request::get("https://jsonplaceholder.typicode.com/todos/")).unwrap();
     println!("{:#?}", response);
     assert_eq!(response.status.unwrap(), 200);
+    assert_eq!(response.text(), "[{}]");
 }
+
+#[test]
+fn test_tls_preflight() {
+    let (mut client, mock) = mock_client(&[concat!(
+        "HTTP/1.1 204 No Content\r\nAllow: GET, HEAD, OPTIONS\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        "HTTP/1.1 204 No Content\r\nAllow: GET, HEAD, OPTIONS\r\n\r\n",
+    )]);
+    client.config.perform_preflight = true;
+
+    let response = client.send(&Request::get("https://jsonplaceholder.typicode.com/todos/")).unwrap();
+    assert_eq!(response.status.unwrap(), 200);
+    assert!(sent(&mock, 0).starts_with("OPTIONS /todos/ HTTP/1.1\r\n"));
+    assert!(sent(&mock, 0).contains("\r\n\r\nGET /todos/ HTTP/1.1\r\n"));
+
+    let refused = client.send(&Request::delete("https://jsonplaceholder.typicode.com/todos/1"));
+    assert!(refused.is_err());
+    assert_eq!(mock.connections().len(), 1);
+    assert!(!sent(&mock, 0).contains("DELETE"));
+}
+
 #[test]
 fn test_tls_put() {
-    let mut request = crate::structs::Request::delete("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md");
-    request.request_type = crate::structs::RequestType::Put;
-    let response = request.send().unwrap();
+    let (mut client, mock) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}"]);
+    let body = PostData::with_content_type("{\"id\": 1, \"title\": \"Curio\", \"body\": \"PUT\", \"userId\": 1}", "application/json").unwrap();
+    let response = client.send(Request::put("https://jsonplaceholder.typicode.com/posts/1").set_body(&body)).unwrap();
     println!("{:#?}", response);
     assert_eq!(response.status.unwrap(), 200);
+    assert!(sent(&mock, 0).starts_with("PUT /posts/1 HTTP/1.1\r\n"));
+    assert!(sent(&mock, 0).ends_with("\r\n\r\n{\"id\": 1, \"title\": \"Curio\", \"body\": \"PUT\", \"userId\": 1}"));
 }
 
 #[test]
 fn test_tls_patch() {
-    let mut request = crate::structs::Request::delete("https://raw.githubusercontent.com/fatalcenturion/Curio/master/README.md");
-    request.request_type = crate::structs::RequestType::Patch;
-    let response = request.send().unwrap();
+    let (mut client, mock) = mock_client(&["HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}"]);
+    let body = PostData::from_tuple(vec!(("title", "Curio")));
+    let response = client.send(Request::patch("https://jsonplaceholder.typicode.com/posts/1").set_body(&body)).unwrap();
     println!("{:#?}", response);
     assert_eq!(response.status.unwrap(), 200);
-}
\ No newline at end of file
+    assert!(sent(&mock, 0).starts_with("PATCH /posts/1 HTTP/1.1\r\n"));
+    assert!(sent(&mock, 0).contains("Content-Length: 11\r\n"));
+    assert!(sent(&mock, 0).ends_with("\r\n\r\ntitle=Curio"));
+}
+
+#[test]
+fn test_tls_custom() {
+    let (mut client, mock) = mock_client(&["HTTP/1.1 207 Multi-Status\r\nContent-Length: 0\r\n\r\n"]);
+    let response = client.send(Request::custom("PROPFIND", "https://dav.example.com/calendars/").set_header("Depth", "1")).unwrap();
+    assert_eq!(response.status.unwrap(), 207);
+    assert!(sent(&mock, 0).starts_with("PROPFIND /calendars/ HTTP/1.1\r\n"));
+    assert!(sent(&mock, 0).contains("Depth: 1\r\n"));
+}
@@ -0,0 +1,195 @@
+//! Transports open the byte streams requests are sent over.
+//!
+//! `NetTransport` is used by default and connects over TCP, wrapping the connection in TLS for HTTPS requests.
+//! `MockTransport` never touches the network, it replays scripted server bytes and records what the client sent,
+//! which makes it possible to test code built on Curio offline:
+//! ```
+//! # use curio::prelude::*;
+//! # use curio::structs::Client;
+//! # use curio::transport::MockTransport;
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let mock = MockTransport::new();
+//!     mock.push_response("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+//!
+//!     let mut client = Client::with_transport(mock.clone());
+//!     client.config.perform_preflight = false;
+//!
+//!     let response = client.send(&Request::get("https://example.com/greeting"))?;
+//!
+//!     assert_eq!(response.text(), "hello");
+//!     assert!(mock.requests()[0].starts_with(b"GET /greeting HTTP/1.1\r\n"));
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::io::{Cursor, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rustls::{ClientSession, StreamOwned};
+
+use crate::types::{Error, Result};
+
+/// A connected, bidirectional byte stream.
+/// Streams which can time out should implement the timeout setters, `None` means reads or writes may block forever.
+pub trait Stream: Read + Write + Send {
+    /// Limits how long a single read may block before failing with `ErrorKind::TimedOut` or `ErrorKind::WouldBlock`
+    fn set_read_timeout(&self, _timeout: Option<Duration>) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Limits how long a single write may block before failing with `ErrorKind::TimedOut` or `ErrorKind::WouldBlock`
+    fn set_write_timeout(&self, _timeout: Option<Duration>) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Stream for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_write_timeout(self, timeout)
+    }
+}
+
+impl Stream for StreamOwned<ClientSession, TcpStream> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        self.sock.set_read_timeout(timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        self.sock.set_write_timeout(timeout)
+    }
+}
+
+/// Opens connections for the client
+pub trait Transport: Send + Sync {
+    /// Opens a stream to `host` on `port`, secured with TLS when `secure` is set, giving up after `timeout` if one is set.
+    /// Failures should be reported with the matching `Error` variant, such as `Error::Dns` or `Error::ConnectionRefused`.
+    fn connect(&self, host: &str, port: usize, secure: bool, timeout: Option<Duration>) -> Result<Box<dyn Stream>>;
+}
+
+/// The default transport: plain TCP for HTTP, and TLS verified against the webpki root certificates for HTTPS
+#[derive(Debug, Clone, Default)]
+pub struct NetTransport;
+
+impl Transport for NetTransport {
+    fn connect(&self, host: &str, port: usize, secure: bool, timeout: Option<Duration>) -> Result<Box<dyn Stream>> {
+        if secure {
+            Ok(Box::new(crate::tls::connect(host, port, timeout)?))
+        } else {
+            Ok(Box::new(crate::tcp::connect(host, port, timeout)?))
+        }
+    }
+}
+
+/// A connection opened through a `MockTransport`
+#[derive(Debug, Clone)]
+pub struct MockConnection {
+    /// The host the client connected to
+    pub host: String,
+    /// The port the client connected to
+    pub port: usize,
+    /// Whether the client asked for a TLS connection
+    pub secure: bool,
+    /// Every byte the client wrote to the connection
+    pub sent: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    scripts: VecDeque<Vec<u8>>,
+    connections: Vec<MockConnection>,
+}
+
+/// An in-memory transport which replays scripted server bytes instead of connecting to the network.
+/// Every connection the client opens consumes the next script pushed with `push_response`, and clones share the same scripts and records.
+/// Once every script has been used, further connections fail with `Error::ConnectionRefused`.
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockTransport {
+    pub fn new() -> MockTransport {
+        MockTransport::default()
+    }
+
+    /// Queues the raw bytes the server sends on the next connection.
+    /// A script can hold several responses back to back, which are read in turn if the connection is reused.
+    pub fn push_response<B: Into<Vec<u8>>>(&self, bytes: B) -> &MockTransport {
+        self.state().scripts.push_back(bytes.into());
+        self
+    }
+
+    /// Every connection opened so far, in the order they were opened
+    pub fn connections(&self) -> Vec<MockConnection> {
+        self.state().connections.clone()
+    }
+
+    /// The bytes the client sent on each connection, in the order the connections were opened
+    pub fn requests(&self) -> Vec<Vec<u8>> {
+        self.state().connections.iter().map(|connection| connection.sent.clone()).collect()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Transport for MockTransport {
+    fn connect(&self, host: &str, port: usize, secure: bool, _timeout: Option<Duration>) -> Result<Box<dyn Stream>> {
+        let mut state = self.state();
+        let script = match state.scripts.pop_front() {
+            Some(script) => script,
+            None => return Err(Error::ConnectionRefused {
+                host: host.to_string(),
+                port,
+                source: std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "MockTransport has no response scripted for this connection"),
+            }),
+        };
+
+        state.connections.push(MockConnection {
+            host: host.to_string(),
+            port,
+            secure,
+            sent: Vec::new(),
+        });
+
+        Ok(Box::new(MockStream {
+            reader: Cursor::new(script),
+            index: state.connections.len() - 1,
+            state: self.state.clone(),
+        }))
+    }
+}
+
+struct MockStream {
+    reader: Cursor<Vec<u8>>,
+    index: usize,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl Stream for MockStream {}
+
+impl Read for MockStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl Write for MockStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.connections[self.index].sent.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
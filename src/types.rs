@@ -1,237 +1,570 @@
 use std::fmt;
 use std::error::Error as StdError;
 
+use crate::structs::Response;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 // This error enum implements codes listed on this page, courtesy of Wikipedia
 // https://en.wikipedia.org/wiki/List_of_HTTP_status_codes
 
+/// Every way a request can fail. Variants caused by another error keep it, and return it from `source`.
 #[derive(Debug)]
 pub enum Error {
     //Any error which is not represented here.
     EXXXUnknownError,
-    //
-    // //Server cannot or will not process the request due to a client error.
-    // E400BadRequest,
-    //
-    // //403 except for if the authentication is invalid.
-    // E401Unauthorized,
-    //
-    // //Reserved for future use, it was supposed ot be used in a digital cash scheme.
-    // E402PaymentRequired,
-    //
-    // //The client is not allowed to make this request due to permission limitations.
-    // E403Forbidden,
-    //
-    // //the requested resource cannot be found on the server.
-    // E404NotFound,
-    //
-    // //the method used is not allowed.
-    // E405MethodNotAllowed,
-    //
-    // //the requested resource is capable of generating only content not acceptable according to the Accept headers sent in the request.
-    // E406NotAcceptable,
-    //
-    // //the client must first authenticate itself with the proxy.
-    // E407ProxyAuthRequired,
-    //
-    // //the request timed out.
-    // E408RequestTimeout,
-    //
-    // //the request could not be processed due to the current state of the requested resource
-    // // (eg: https://en.wikipedia.org/wiki/Edit_conflict)
-    // E409Conflict,
-    //
-    // //the requested resource is no longer available and will not be available again. This should indicate that the client shouldnt continue requesting it.
-    // E410Gone,
-    //
-    // //the request did not specify the length of the content, which is required by the resource.
-    // E411LengthRequired,
-    //
-    // //the server does not meet one of the preconditions the client put in the header.
-    // E412PreconditionFailed,
-    //
-    // //the request is larger than the server is willing or able to process.
-    // E413PayloadTooLarge,
-    //
-    // //the uri provided was too long to process, this is usually the result of a long query string in the uri.
-    // E414URITooLong,
-    //
-    // //the request entity has a media type which the server or resource does not support.
-    // E415UnsupportedMediaType,
-    //
-    // //the client asked for a portion of the file which cannot be supplied by the server.
-    // E416RangeNotSatisfiable,
-    //
-    // //the server does not meet the requirements of the `expect` header field.
-    // E417ExpectationFailed,
-    //
-    // //this code is almost certainly never going to be used, but, it is documented so im having it here. search for it on wikipedia.
-    // E418ImATeapot,
-    //
-    // //the request was directed at a server which is not able to produce a response (eg: if the server refused a connection).
-    // E421MisdirectedRequest,
-    //
-    // //the request was formatted properly, but could not be followed due to semantic errors.
-    // E422UnprocessableEntity,
-    //
-    // //the resource being accessed is locked.
-    // E423Locked,
-    //
-    // //the request failed because it depended on a different request which also failed.
-    // E424FailedDependency,
-    //
-    // //the server is unwilling to risk processing a request that might be replayed.
-    // E425TooEarly,
-    //
-    // //the client should switch to a different protocol such as TLS/1.0, this is in the upgrade header field.
-    // E426UpgradeRequired,
-    //
-    // //the server requires the request to be conditional.
-    // E428PreconditionRequired,
-    //
-    // //the client has sent too many requests in a given amount of time. used with rate-limiting schemes.
-    // E429TooManyRequests,
-    //
-    // //the server is unwilling to process the request because either an individual header field, or all of them collectively, are too large.
-    // E431RequestHeaderFieldsTooLarge,
-    //
-    // //the server operator has receved a legal demand to deny access to a resource or a set of resource including the request resource. A referece to the novel "Fahrenheit 451".
-    // E451UnavailableForLegalReasons,
-    //
-    // //the server encountered an error and failed to fulfill the request.
-    // E500InternalServerError,
-    //
-    // //the server does not recognize the method, or lacks the ability to fulfill the request. (ususally implies that it will be available in the future.)
-    // E501NotImplemented,
+
+    //Server cannot or will not process the request due to a client error.
+    E400BadRequest(Box<Response>),
+
+    //403 except for if the authentication is invalid.
+    E401Unauthorized(Box<Response>),
+
+    //Reserved for future use, it was supposed ot be used in a digital cash scheme.
+    E402PaymentRequired(Box<Response>),
+
+    //The client is not allowed to make this request due to permission limitations.
+    E403Forbidden(Box<Response>),
+
+    //the requested resource cannot be found on the server.
+    E404NotFound(Box<Response>),
+
+    //the method used is not allowed.
+    E405MethodNotAllowed(Box<Response>),
+
+    //the requested resource is capable of generating only content not acceptable according to the Accept headers sent in the request.
+    E406NotAcceptable(Box<Response>),
+
+    //the client must first authenticate itself with the proxy.
+    E407ProxyAuthRequired(Box<Response>),
+
+    //the request timed out.
+    E408RequestTimeout(Box<Response>),
+
+    //the request could not be processed due to the current state of the requested resource
+    // (eg: https://en.wikipedia.org/wiki/Edit_conflict)
+    E409Conflict(Box<Response>),
+
+    //the requested resource is no longer available and will not be available again. This should indicate that the client shouldnt continue requesting it.
+    E410Gone(Box<Response>),
+
+    //the request did not specify the length of the content, which is required by the resource.
+    E411LengthRequired(Box<Response>),
+
+    //the server does not meet one of the preconditions the client put in the header.
+    E412PreconditionFailed(Box<Response>),
+
+    //the request is larger than the server is willing or able to process.
+    E413PayloadTooLarge(Box<Response>),
+
+    //the uri provided was too long to process, this is usually the result of a long query string in the uri.
+    E414URITooLong(Box<Response>),
+
+    //the request entity has a media type which the server or resource does not support.
+    E415UnsupportedMediaType(Box<Response>),
+
+    //the client asked for a portion of the file which cannot be supplied by the server.
+    E416RangeNotSatisfiable(Box<Response>),
+
+    //the server does not meet the requirements of the `expect` header field.
+    E417ExpectationFailed(Box<Response>),
+
+    //this code is almost certainly never going to be used, but, it is documented so im having it here. search for it on wikipedia.
+    E418ImATeapot(Box<Response>),
+
+    //the request was directed at a server which is not able to produce a response (eg: if the server refused a connection).
+    E421MisdirectedRequest(Box<Response>),
+
+    //the request was formatted properly, but could not be followed due to semantic errors.
+    E422UnprocessableEntity(Box<Response>),
+
+    //the resource being accessed is locked.
+    E423Locked(Box<Response>),
+
+    //the request failed because it depended on a different request which also failed.
+    E424FailedDependency(Box<Response>),
+
+    //the server is unwilling to risk processing a request that might be replayed.
+    E425TooEarly(Box<Response>),
+
+    //the client should switch to a different protocol such as TLS/1.0, this is in the upgrade header field.
+    E426UpgradeRequired(Box<Response>),
+
+    //the server requires the request to be conditional.
+    E428PreconditionRequired(Box<Response>),
+
+    //the client has sent too many requests in a given amount of time. used with rate-limiting schemes.
+    E429TooManyRequests(Box<Response>),
+
+    //the server is unwilling to process the request because either an individual header field, or all of them collectively, are too large.
+    E431RequestHeaderFieldsTooLarge(Box<Response>),
+
+    //the server operator has receved a legal demand to deny access to a resource or a set of resource including the request resource. A referece to the novel "Fahrenheit 451".
+    E451UnavailableForLegalReasons(Box<Response>),
+
+    //the server encountered an error and failed to fulfill the request.
+    E500InternalServerError(Box<Response>),
+
+    //the server does not recognize the method, or lacks the ability to fulfill the request. (ususally implies that it will be available in the future.)
+    E501NotImplemented(Box<Response>),
+
+    //the server was acting as a gateway or proxy and received an invalid response from the upstream server.
+    E502BadGateway(Box<Response>),
+
+    //the server cannot handle the request right now, usually because it is overloaded or down for maintenance.
+    E503ServiceUnavailable(Box<Response>),
+
+    //the server was acting as a gateway or proxy and did not receive a timely response from the upstream server.
+    E504GatewayTimeout(Box<Response>),
+
+    //the server does not support the HTTP protocol version used in the request.
+    E505HTTPVersionNotSupported(Box<Response>),
+
+    //transparent content negotiation for the request results in a circular reference.
+    E506VariantAlsoNegotiates(Box<Response>),
+
+    //the server is unable to store the representation needed to complete the request.
+    E507InsufficientStorage(Box<Response>),
+
+    //the server detected an infinite loop while processing the request.
+    E508LoopDetected(Box<Response>),
+
+    //further extensions to the request are required for the server to fulfill it.
+    E510NotExtended(Box<Response>),
+
+    //the client needs to authenticate to gain network access (eg: a captive portal).
+    E511NetworkAuthenticationRequired(Box<Response>),
+
+    //any other 4xx status code.
+    E4XXClientError(Box<Response>),
+
+    //any other 5xx status code.
+    E5XXServerError(Box<Response>),
 
     //the server has indicated that the method is not allowed by the origin.
     CrossOriginResourceMethodDisallowed,
 
     //the server has indicated that the origin is not allowed to access it.
     CrossOriginResourceOriginDisallowed,
+
+    //the request method is not a valid HTTP token, is not supported, or cannot carry the body it was given.
+    InvalidMethod(String),
+
+    //the url could not be parsed, or uses a scheme other than http and https.
+    InvalidUrl { url: String, reason: String },
+
+    //the host name could not be resolved to an address.
+    Dns { host: String, source: std::io::Error },
+
+    //the server refused the connection, usually because nothing is listening on the port.
+    ConnectionRefused { host: String, port: usize, source: std::io::Error },
+
+    //the connection could not be established for any other reason (eg: the network is unreachable).
+    Connect { host: String, port: usize, source: std::io::Error },
+
+    //the TLS handshake failed, or the host name cannot be used to verify the server.
+    TlsHandshake { host: String, source: Box<dyn StdError + Send + Sync> },
+
+    //the certificate presented by the server could not be verified.
+    Certificate { host: String, source: rustls::TLSError },
+
+    //the request ran out of time, `phase` says which of its timeouts elapsed.
+    Timeout { phase: TimeoutPhase, source: std::io::Error },
+
+    //the server sent a response which does not follow the HTTP/1.1 message syntax.
+    MalformedResponse { reason: String, source: Option<Box<dyn StdError + Send + Sync>> },
+
+    //the request was redirected more times than the configured limit allows.
+    TooManyRedirects { limit: u8 },
+
+    //the response body is larger than the configured limit.
+    BodyTooLarge { limit: u64 },
+
+    //the connection failed while the request was being sent or the response was being read.
+    Io(std::io::Error),
+
+    //the response body uses a character set which Curio cannot decode.
+    UnsupportedCharset(String),
+
+    //the streamed request body was already sent, so the request cannot be sent again.
+    BodyNotRewindable,
+
+    //a header name is not a valid token, or its value contains a line break or another control character.
+    InvalidHeader { name: String, reason: String },
+
+    //a `Set-Cookie` header could not be parsed as a cookie, `reason` says why.
+    InvalidCookie { cookie: String, reason: String },
+
+    //a value could not be serialized as JSON, or a response body is not the JSON value which was expected. `status` and `excerpt` describe the response.
+    #[cfg(feature = "json")]
+    Json { status: Option<isize>, excerpt: String, source: serde_json::Error },
+}
+
+impl Error {
+    pub(crate) fn timeout<S: Into<String>>(phase: TimeoutPhase, message: S) -> Error {
+        Error::Timeout { phase, source: std::io::Error::new(std::io::ErrorKind::TimedOut, message.into()) }
+    }
+
+    /// Reports a timeout as belonging to `phase`, any other error is returned unchanged
+    pub(crate) fn in_phase(self, phase: TimeoutPhase) -> Error {
+        match self {
+            Error::Timeout { source, .. } => Error::Timeout { phase, source },
+            other => other,
+        }
+    }
+
+    /// The response which caused a status code error, such as `E404NotFound`, so that its body and headers can still be inspected
+    pub fn response(&self) -> Option<&Response> {
+        match self {
+            Error::E400BadRequest(response)
+            | Error::E401Unauthorized(response)
+            | Error::E402PaymentRequired(response)
+            | Error::E403Forbidden(response)
+            | Error::E404NotFound(response)
+            | Error::E405MethodNotAllowed(response)
+            | Error::E406NotAcceptable(response)
+            | Error::E407ProxyAuthRequired(response)
+            | Error::E408RequestTimeout(response)
+            | Error::E409Conflict(response)
+            | Error::E410Gone(response)
+            | Error::E411LengthRequired(response)
+            | Error::E412PreconditionFailed(response)
+            | Error::E413PayloadTooLarge(response)
+            | Error::E414URITooLong(response)
+            | Error::E415UnsupportedMediaType(response)
+            | Error::E416RangeNotSatisfiable(response)
+            | Error::E417ExpectationFailed(response)
+            | Error::E418ImATeapot(response)
+            | Error::E421MisdirectedRequest(response)
+            | Error::E422UnprocessableEntity(response)
+            | Error::E423Locked(response)
+            | Error::E424FailedDependency(response)
+            | Error::E425TooEarly(response)
+            | Error::E426UpgradeRequired(response)
+            | Error::E428PreconditionRequired(response)
+            | Error::E429TooManyRequests(response)
+            | Error::E431RequestHeaderFieldsTooLarge(response)
+            | Error::E451UnavailableForLegalReasons(response)
+            | Error::E500InternalServerError(response)
+            | Error::E501NotImplemented(response)
+            | Error::E502BadGateway(response)
+            | Error::E503ServiceUnavailable(response)
+            | Error::E504GatewayTimeout(response)
+            | Error::E505HTTPVersionNotSupported(response)
+            | Error::E506VariantAlsoNegotiates(response)
+            | Error::E507InsufficientStorage(response)
+            | Error::E508LoopDetected(response)
+            | Error::E510NotExtended(response)
+            | Error::E511NetworkAuthenticationRequired(response)
+            | Error::E4XXClientError(response)
+            | Error::E5XXServerError(response) => Some(response),
+            _ => None,
+        }
+    }
+
+    /// Takes the response out of a status code error
+    pub fn into_response(self) -> Option<Response> {
+        match self {
+            Error::E400BadRequest(response)
+            | Error::E401Unauthorized(response)
+            | Error::E402PaymentRequired(response)
+            | Error::E403Forbidden(response)
+            | Error::E404NotFound(response)
+            | Error::E405MethodNotAllowed(response)
+            | Error::E406NotAcceptable(response)
+            | Error::E407ProxyAuthRequired(response)
+            | Error::E408RequestTimeout(response)
+            | Error::E409Conflict(response)
+            | Error::E410Gone(response)
+            | Error::E411LengthRequired(response)
+            | Error::E412PreconditionFailed(response)
+            | Error::E413PayloadTooLarge(response)
+            | Error::E414URITooLong(response)
+            | Error::E415UnsupportedMediaType(response)
+            | Error::E416RangeNotSatisfiable(response)
+            | Error::E417ExpectationFailed(response)
+            | Error::E418ImATeapot(response)
+            | Error::E421MisdirectedRequest(response)
+            | Error::E422UnprocessableEntity(response)
+            | Error::E423Locked(response)
+            | Error::E424FailedDependency(response)
+            | Error::E425TooEarly(response)
+            | Error::E426UpgradeRequired(response)
+            | Error::E428PreconditionRequired(response)
+            | Error::E429TooManyRequests(response)
+            | Error::E431RequestHeaderFieldsTooLarge(response)
+            | Error::E451UnavailableForLegalReasons(response)
+            | Error::E500InternalServerError(response)
+            | Error::E501NotImplemented(response)
+            | Error::E502BadGateway(response)
+            | Error::E503ServiceUnavailable(response)
+            | Error::E504GatewayTimeout(response)
+            | Error::E505HTTPVersionNotSupported(response)
+            | Error::E506VariantAlsoNegotiates(response)
+            | Error::E507InsufficientStorage(response)
+            | Error::E508LoopDetected(response)
+            | Error::E510NotExtended(response)
+            | Error::E511NetworkAuthenticationRequired(response)
+            | Error::E4XXClientError(response)
+            | Error::E5XXServerError(response) => Some(*response),
+            _ => None,
+        }
+    }
+
+    /// The status code of the response which caused a status code error, or whose body could not be parsed as JSON
+    pub fn status(&self) -> Option<isize> {
+        match self {
+            #[cfg(feature = "json")]
+            Error::Json { status, .. } => *status,
+            _ => self.response().and_then(|response| response.status),
+        }
+    }
+
+    pub(crate) fn malformed<S: Into<String>>(reason: S) -> Error {
+        Error::MalformedResponse { reason: reason.into(), source: None }
+    }
+
+    pub(crate) fn malformed_by<S: Into<String>, E: Into<Box<dyn StdError + Send + Sync>>>(reason: S, source: E) -> Error {
+        Error::MalformedResponse { reason: reason.into(), source: Some(source.into()) }
+    }
+}
+
+/// The part of a request which took too long, see `ClientConfig::timeouts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPhase {
+    /// Resolving the host, connecting and completing the TLS handshake
+    Connect,
+    /// Waiting for a single read from the server
+    Read,
+    /// Waiting for a single write to the server
+    Write,
+    /// The whole request, including preflight requests, redirects and the body download
+    Total,
+}
+
+impl fmt::Display for TimeoutPhase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeoutPhase::Connect => f.write_str("connect"),
+            TimeoutPhase::Read => f.write_str("read"),
+            TimeoutPhase::Write => f.write_str("write"),
+            TimeoutPhase::Total => f.write_str("total"),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        match e.kind() {
+            std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => Error::Timeout { phase: TimeoutPhase::Read, source: e },
+            _ => Error::Io(e),
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
+        match self {
             Error::EXXXUnknownError => f.write_str("Unknown Error - Curio does not have a definition for this error"),
             Error::CrossOriginResourceMethodDisallowed => f.write_str("Cross Origin Resource Error"),
             Error::CrossOriginResourceOriginDisallowed => f.write_str("Cross Origin Resource Error"),
-            // Error::E400BadRequest => f.write_str("HTTP 400 - Bad Request"),
-            // Error::E401Unauthorized => f.write_str("HTTP 401 - Unauthorized"),
-            // Error::E402PaymentRequired => f.write_str("HTTP 402 - Payment Required"),
-            // Error::E403Forbidden => f.write_str("HTTP 403 - Forbidden"),
-            // Error::E404NotFound => f.write_str("HTTP 404 - Not Found"),
-            // Error::E405MethodNotAllowed => f.write_str("HTTP 405 - Method Not Allowed"),
-            // Error::E406NotAcceptable => f.write_str("HTTP 406 - Not Acceptable"),
-            // Error::E407ProxyAuthRequired => f.write_str("HTTP 407 - Proxy Auth Required"),
-            // Error::E408RequestTimeout => f.write_str("HTTP 408 - Request Timeout"),
-            // Error::E409Conflict => f.write_str("HTTP 409 - Conflict"),
-            // Error::E410Gone => f.write_str("HTTP 410 - Gone"),
-            // Error::E411LengthRequired => f.write_str("HTTP 411 - Length Required"),
-            // Error::E412PreconditionFailed => f.write_str("HTTP 412 - Precondition Failed"),
-            // Error::E413PayloadTooLarge => f.write_str("HTTP 413 - Payload Too Large"),
-            // Error::E414URITooLong => f.write_str("HTTP 414 - URI Too Long"),
-            // Error::E415UnsupportedMediaType => f.write_str("HTTP 415 - Unsupported Media Type"),
-            // Error::E416RangeNotSatisfiable => f.write_str("HTTP 416 - Range Not Satisfiable"),
-            // Error::E417ExpectationFailed => f.write_str("HTTP 417 - Expectation Failed"),
-            // Error::E418ImATeapot => f.write_str("HTTP 418 - I'm A Teapot"),
-            // Error::E421MisdirectedRequest => f.write_str("HTTP 421 - Misdirected Request"),
-            // Error::E422UnprocessableEntity => f.write_str("HTTP 422 - Unprocessable Entity"),
-            // Error::E423Locked => f.write_str("HTTP 423 - Locked"),
-            // Error::E424FailedDependency => f.write_str("HTTP 425 - Failed Dependancy"),
-            // Error::E425TooEarly => f.write_str("HTTP 425 - Too Early"),
-            // Error::E426UpgradeRequired => f.write_str("HTTP 426 - Upgrade Required"),
-            // Error::E428PreconditionRequired => f.write_str("HTTP 428 - Precondition Required"),
-            // Error::E429TooManyRequests => f.write_str("HTTP 429 - Too Many Requests"),
-            // Error::E431RequestHeaderFieldsTooLarge => f.write_str("HTTP 431 - Request Header Fields Too Large"),
-            // Error::E451UnavailableForLegalReasons => f.write_str("HTTP 451 - Unavailable For Legal Reasons"),
-            // Error::E500InternalServerError => f.write_str("HTTP 500 - Internal Server Error"),
-            // Error::E501NotImplemented => f.write_str("HTTP 501 - Not Implemented"),
-            _ => f.write_str("Unknown Error - Curio does not have a definition for this error")
+            Error::InvalidMethod(method) => write!(f, "Invalid Method - {:?} cannot be sent by Curio", method),
+            Error::InvalidUrl { url, reason } => write!(f, "Invalid URL - {:?}: {}", url, reason),
+            Error::Dns { host, source } => write!(f, "DNS Error - {} could not be resolved: {}", host, source),
+            Error::ConnectionRefused { host, port, source } => write!(f, "Connection Refused - {}:{}: {}", host, port, source),
+            Error::Connect { host, port, source } => write!(f, "Connection Error - {}:{}: {}", host, port, source),
+            Error::TlsHandshake { host, source } => write!(f, "TLS Handshake Error - {}: {}", host, source),
+            Error::Certificate { host, source } => write!(f, "Certificate Error - {}: {}", host, source),
+            Error::Timeout { phase, source } => write!(f, "Timeout - the {} timeout elapsed: {}", phase, source),
+            Error::MalformedResponse { reason, source: Some(source) } => write!(f, "Malformed Response - {}: {}", reason, source),
+            Error::MalformedResponse { reason, source: None } => write!(f, "Malformed Response - {}", reason),
+            Error::TooManyRedirects { limit } => write!(f, "Too Many Redirects - the limit of {} was reached", limit),
+            Error::BodyTooLarge { limit } => write!(f, "Body Too Large - the response body exceeds the limit of {} bytes", limit),
+            Error::Io(source) => write!(f, "IO Error - {}", source),
+            Error::UnsupportedCharset(charset) => write!(f, "Unsupported Charset - {:?} cannot be decoded by Curio", charset),
+            Error::BodyNotRewindable => write!(f, "Body Not Rewindable - a streamed request body can only be sent once"),
+            Error::InvalidHeader { name, reason } => write!(f, "Invalid Header - {:?}: {}", name, reason),
+            Error::InvalidCookie { cookie, reason } => write!(f, "Invalid Cookie - {:?}: {}", cookie, reason),
+            #[cfg(feature = "json")]
+            Error::Json { status: Some(status), excerpt, source } => write!(f, "JSON Error - the body of the {} response could not be parsed: {} (body: {:?})", status, source, excerpt),
+            #[cfg(feature = "json")]
+            Error::Json { status: None, source, .. } => write!(f, "JSON Error - {}", source),
+            Error::E400BadRequest(_) => f.write_str("HTTP 400 - Bad Request"),
+            Error::E401Unauthorized(_) => f.write_str("HTTP 401 - Unauthorized"),
+            Error::E402PaymentRequired(_) => f.write_str("HTTP 402 - Payment Required"),
+            Error::E403Forbidden(_) => f.write_str("HTTP 403 - Forbidden"),
+            Error::E404NotFound(_) => f.write_str("HTTP 404 - Not Found"),
+            Error::E405MethodNotAllowed(_) => f.write_str("HTTP 405 - Method Not Allowed"),
+            Error::E406NotAcceptable(_) => f.write_str("HTTP 406 - Not Acceptable"),
+            Error::E407ProxyAuthRequired(_) => f.write_str("HTTP 407 - Proxy Auth Required"),
+            Error::E408RequestTimeout(_) => f.write_str("HTTP 408 - Request Timeout"),
+            Error::E409Conflict(_) => f.write_str("HTTP 409 - Conflict"),
+            Error::E410Gone(_) => f.write_str("HTTP 410 - Gone"),
+            Error::E411LengthRequired(_) => f.write_str("HTTP 411 - Length Required"),
+            Error::E412PreconditionFailed(_) => f.write_str("HTTP 412 - Precondition Failed"),
+            Error::E413PayloadTooLarge(_) => f.write_str("HTTP 413 - Payload Too Large"),
+            Error::E414URITooLong(_) => f.write_str("HTTP 414 - URI Too Long"),
+            Error::E415UnsupportedMediaType(_) => f.write_str("HTTP 415 - Unsupported Media Type"),
+            Error::E416RangeNotSatisfiable(_) => f.write_str("HTTP 416 - Range Not Satisfiable"),
+            Error::E417ExpectationFailed(_) => f.write_str("HTTP 417 - Expectation Failed"),
+            Error::E418ImATeapot(_) => f.write_str("HTTP 418 - I'm A Teapot"),
+            Error::E421MisdirectedRequest(_) => f.write_str("HTTP 421 - Misdirected Request"),
+            Error::E422UnprocessableEntity(_) => f.write_str("HTTP 422 - Unprocessable Entity"),
+            Error::E423Locked(_) => f.write_str("HTTP 423 - Locked"),
+            Error::E424FailedDependency(_) => f.write_str("HTTP 424 - Failed Dependency"),
+            Error::E425TooEarly(_) => f.write_str("HTTP 425 - Too Early"),
+            Error::E426UpgradeRequired(_) => f.write_str("HTTP 426 - Upgrade Required"),
+            Error::E428PreconditionRequired(_) => f.write_str("HTTP 428 - Precondition Required"),
+            Error::E429TooManyRequests(_) => f.write_str("HTTP 429 - Too Many Requests"),
+            Error::E431RequestHeaderFieldsTooLarge(_) => f.write_str("HTTP 431 - Request Header Fields Too Large"),
+            Error::E451UnavailableForLegalReasons(_) => f.write_str("HTTP 451 - Unavailable For Legal Reasons"),
+            Error::E500InternalServerError(_) => f.write_str("HTTP 500 - Internal Server Error"),
+            Error::E501NotImplemented(_) => f.write_str("HTTP 501 - Not Implemented"),
+            Error::E502BadGateway(_) => f.write_str("HTTP 502 - Bad Gateway"),
+            Error::E503ServiceUnavailable(_) => f.write_str("HTTP 503 - Service Unavailable"),
+            Error::E504GatewayTimeout(_) => f.write_str("HTTP 504 - Gateway Timeout"),
+            Error::E505HTTPVersionNotSupported(_) => f.write_str("HTTP 505 - HTTP Version Not Supported"),
+            Error::E506VariantAlsoNegotiates(_) => f.write_str("HTTP 506 - Variant Also Negotiates"),
+            Error::E507InsufficientStorage(_) => f.write_str("HTTP 507 - Insufficient Storage"),
+            Error::E508LoopDetected(_) => f.write_str("HTTP 508 - Loop Detected"),
+            Error::E510NotExtended(_) => f.write_str("HTTP 510 - Not Extended"),
+            Error::E511NetworkAuthenticationRequired(_) => f.write_str("HTTP 511 - Network Authentication Required"),
+            Error::E4XXClientError(response) | Error::E5XXServerError(response) => write!(f, "HTTP {} - {}", response.status.unwrap_or(0), response.status_text.as_deref().unwrap_or("")),
         }
     }
 }
 
 impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Dns { source, .. } => Some(source),
+            Error::ConnectionRefused { source, .. } => Some(source),
+            Error::Connect { source, .. } => Some(source),
+            Error::TlsHandshake { source, .. } => Some(source.as_ref()),
+            Error::Certificate { source, .. } => Some(source),
+            Error::Timeout { source, .. } => Some(source),
+            Error::MalformedResponse { source: Some(source), .. } => Some(source.as_ref()),
+            Error::Io(source) => Some(source),
+            #[cfg(feature = "json")]
+            Error::Json { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+
     fn description(&self) -> &str {
-        match *self {
+        match self {
             Error::CrossOriginResourceMethodDisallowed => "The method selected for this request is disallowed by the server",
             Error::CrossOriginResourceOriginDisallowed => "The current origin of the connection is not allowed to request the resource",
             Error::EXXXUnknownError => "The error code is not implemented, try reading up, the log should be written there.",
-            // Error::E400BadRequest => "The request was malformed",
-            // Error::E401Unauthorized => "The authentication provided is not valid and the request has been denied",
-            // Error::E402PaymentRequired => "The endpoint requires payment - if you got this error then i have no clue what you are doing as it is a reserved code.",
-            // Error::E403Forbidden => "The client is forbidden from accessing this resource without authentication",
-            // Error::E404NotFound => "The requested resource is unable to be located",
-            // Error::E405MethodNotAllowed => "The method used to request the resource, is not allowed",
-            // Error::E406NotAcceptable => "The request could not produce an acceptable response due to the content of the `accept` header provided",
-            // Error::E407ProxyAuthRequired => "The client must first authenticate with the proxy",
-            // Error::E408RequestTimeout => "The request timed out",
-            // Error::E409Conflict => "Access to the requested resource was denied to prevent a conflict of data",
-            // Error::E410Gone => "The requested resource is no longer available and should not be requested again",
-            // Error::E411LengthRequired => "The client did not specify a body length and the request was denied",
-            // Error::E412PreconditionFailed => "The server does not meet one of the conditions provided by the client",
-            // Error::E413PayloadTooLarge => "The request is larger than the server is willing, or able to process",
-            // Error::E414URITooLong => "The provided uri was too long, the server is unwilling, or unable to process it. if it includes a query string maybe shorten it?",
-            // Error::E415UnsupportedMediaType => "The request entity has a media type which cannot be processed by the server",
-            // Error::E416RangeNotSatisfiable => "The requested portion of the resource cannot be supplied by the server for some reason",
-            // Error::E417ExpectationFailed => "The serer cannot meet the requirements of the Expect header in the request",
-            // Error::E418ImATeapot => "This response code is an april fools joke from 1998. Get with the times Boomer",
-            // Error::E421MisdirectedRequest => "The request was directed at a server which is unable to produce a response",
-            // Error::E422UnprocessableEntity => "The request was well formed, but unable to be followed due to semantic errors",
-            // Error::E423Locked => "The requested resource is locked",
-            // Error::E424FailedDependency => "The request failed because it relied on a previous request, which also failed",
-            // Error::E425TooEarly => "The server is unwilling to risk processing a request which might be replayed",
-            // Error::E426UpgradeRequired => "The client should switch to a different protocol (eg: TLS/1.0) specified in the `Upgrade` header field",
-            // Error::E428PreconditionRequired => "The server requires a request to be conditional.",
-            // Error::E429TooManyRequests => "The client has sent too many requests in a given amount of time. Generally used with rate-limiting schemes",
-            // Error::E431RequestHeaderFieldsTooLarge => "The client sent a request containing one or more header fields which the server is unable to process due to size limitations",
-            // Error::E451UnavailableForLegalReasons => "The server operator has received a legal demand to deny access to a resource, or set of resources including this resource.",
-            // Error::E500InternalServerError => "The server encountered an error and was unable to process the request",
-            // Error::E501NotImplemented => "The server either does not recognize the request method, or lacks the ability to fulfill the request. (this usually implies it will be available in the future)",
-            _ => "The error code is not implemented, try reading up, the log should be written there."
+            Error::InvalidMethod(_) => "The request method is not a valid HTTP token, is not supported, or does not allow a request body",
+            Error::InvalidUrl { .. } => "The url could not be parsed, or does not use the http or https scheme",
+            Error::Dns { .. } => "The host name could not be resolved to an address",
+            Error::ConnectionRefused { .. } => "The server refused the connection",
+            Error::Connect { .. } => "The connection to the server could not be established",
+            Error::TlsHandshake { .. } => "The TLS handshake with the server failed",
+            Error::Certificate { .. } => "The certificate presented by the server could not be verified",
+            Error::Timeout { .. } => "The request did not complete within one of its timeouts",
+            Error::MalformedResponse { .. } => "The server sent a response which is not valid HTTP/1.1",
+            Error::TooManyRedirects { .. } => "The request was redirected more times than the redirect limit allows",
+            Error::BodyTooLarge { .. } => "The response body is larger than the configured limit",
+            Error::Io(_) => "The connection failed while the request was being sent or the response was being read",
+            Error::UnsupportedCharset(_) => "The response body uses a character set which cannot be decoded",
+            Error::BodyNotRewindable => "A streamed request body can only be sent once",
+            Error::InvalidHeader { .. } => "A header name or value is not valid in HTTP/1.1",
+            Error::InvalidCookie { .. } => "A Set-Cookie header could not be parsed as a cookie",
+            #[cfg(feature = "json")]
+            Error::Json { .. } => "A value could not be converted to or from JSON",
+            Error::E400BadRequest(_) => "The request was malformed",
+            Error::E401Unauthorized(_) => "The authentication provided is not valid and the request has been denied",
+            Error::E402PaymentRequired(_) => "The endpoint requires payment - if you got this error then i have no clue what you are doing as it is a reserved code.",
+            Error::E403Forbidden(_) => "The client is forbidden from accessing this resource without authentication",
+            Error::E404NotFound(_) => "The requested resource is unable to be located",
+            Error::E405MethodNotAllowed(_) => "The method used to request the resource, is not allowed",
+            Error::E406NotAcceptable(_) => "The request could not produce an acceptable response due to the content of the `accept` header provided",
+            Error::E407ProxyAuthRequired(_) => "The client must first authenticate with the proxy",
+            Error::E408RequestTimeout(_) => "The request timed out",
+            Error::E409Conflict(_) => "Access to the requested resource was denied to prevent a conflict of data",
+            Error::E410Gone(_) => "The requested resource is no longer available and should not be requested again",
+            Error::E411LengthRequired(_) => "The client did not specify a body length and the request was denied",
+            Error::E412PreconditionFailed(_) => "The server does not meet one of the conditions provided by the client",
+            Error::E413PayloadTooLarge(_) => "The request is larger than the server is willing, or able to process",
+            Error::E414URITooLong(_) => "The provided uri was too long, the server is unwilling, or unable to process it. if it includes a query string maybe shorten it?",
+            Error::E415UnsupportedMediaType(_) => "The request entity has a media type which cannot be processed by the server",
+            Error::E416RangeNotSatisfiable(_) => "The requested portion of the resource cannot be supplied by the server for some reason",
+            Error::E417ExpectationFailed(_) => "The serer cannot meet the requirements of the Expect header in the request",
+            Error::E418ImATeapot(_) => "This response code is an april fools joke from 1998. Get with the times Boomer",
+            Error::E421MisdirectedRequest(_) => "The request was directed at a server which is unable to produce a response",
+            Error::E422UnprocessableEntity(_) => "The request was well formed, but unable to be followed due to semantic errors",
+            Error::E423Locked(_) => "The requested resource is locked",
+            Error::E424FailedDependency(_) => "The request failed because it relied on a previous request, which also failed",
+            Error::E425TooEarly(_) => "The server is unwilling to risk processing a request which might be replayed",
+            Error::E426UpgradeRequired(_) => "The client should switch to a different protocol (eg: TLS/1.0) specified in the `Upgrade` header field",
+            Error::E428PreconditionRequired(_) => "The server requires a request to be conditional.",
+            Error::E429TooManyRequests(_) => "The client has sent too many requests in a given amount of time. Generally used with rate-limiting schemes",
+            Error::E431RequestHeaderFieldsTooLarge(_) => "The client sent a request containing one or more header fields which the server is unable to process due to size limitations",
+            Error::E451UnavailableForLegalReasons(_) => "The server operator has received a legal demand to deny access to a resource, or set of resources including this resource.",
+            Error::E500InternalServerError(_) => "The server encountered an error and was unable to process the request",
+            Error::E501NotImplemented(_) => "The server either does not recognize the request method, or lacks the ability to fulfill the request. (this usually implies it will be available in the future)",
+            Error::E502BadGateway(_) => "The server was acting as a gateway and received an invalid response from the upstream server",
+            Error::E503ServiceUnavailable(_) => "The server is unable to handle the request right now, it may be overloaded or down for maintenance",
+            Error::E504GatewayTimeout(_) => "The server was acting as a gateway and did not receive a response from the upstream server in time",
+            Error::E505HTTPVersionNotSupported(_) => "The server does not support the HTTP version used by the request",
+            Error::E506VariantAlsoNegotiates(_) => "Content negotiation for the request resulted in a circular reference",
+            Error::E507InsufficientStorage(_) => "The server is unable to store the data needed to complete the request",
+            Error::E508LoopDetected(_) => "The server detected an infinite loop while processing the request",
+            Error::E510NotExtended(_) => "The server requires further extensions to the request to fulfill it",
+            Error::E511NetworkAuthenticationRequired(_) => "The client must authenticate to gain access to the network",
+            Error::E4XXClientError(_) => "The server refused the request because of a client error",
+            Error::E5XXServerError(_) => "The server encountered an error and was unable to process the request",
         }
     }
 }
 
-pub(crate) fn err_from_code(code: u16) -> Error {
-    return match code {
-        0 => Error::EXXXUnknownError,
-        1 => Error::CrossOriginResourceMethodDisallowed,
-        2 => Error::CrossOriginResourceOriginDisallowed,
-        // 400 => Error::E400BadRequest,
-        // 401 => Error::E401Unauthorized,
-        // 402 => Error::E402PaymentRequired,
-        // 403 => Error::E403Forbidden,
-        // 404 => Error::E404NotFound,
-        // 405 => Error::E405MethodNotAllowed,
-        // 406 => Error::E406NotAcceptable,
-        // 407 => Error::E407ProxyAuthRequired,
-        // 408 => Error::E408RequestTimeout,
-        // 409 => Error::E409Conflict,
-        // 410 => Error::E410Gone,
-        // 411 => Error::E411LengthRequired,
-        // 412 => Error::E412PreconditionFailed,
-        // 413 => Error::E413PayloadTooLarge,
-        // 414 => Error::E414URITooLong,
-        // 415 => Error::E415UnsupportedMediaType,
-        // 416 => Error::E416RangeNotSatisfiable,
-        // 417 => Error::E417ExpectationFailed,
-        // 418 => Error::E418ImATeapot,
-        // 421 => Error::E421MisdirectedRequest,
-        // 422 => Error::E422UnprocessableEntity,
-        // 423 => Error::E423Locked,
-        // 424 => Error::E424FailedDependency,
-        // 425 => Error::E425TooEarly,
-        // 426 => Error::E426UpgradeRequired,
-        // 428 => Error::E428PreconditionRequired,
-        // 429 => Error::E429TooManyRequests,
-        // 431 => Error::E431RequestHeaderFieldsTooLarge,
-        // 451 => Error::E451UnavailableForLegalReasons,
-        // 500 => Error::E500InternalServerError,
-        // 501 => Error::E501NotImplemented,
-        _ => Error::EXXXUnknownError,
-    };
+/// Turns a response with a `4xx` or `5xx` status into the matching error, which owns the response
+pub(crate) fn err_from_code(response: Response) -> Error {
+    let response = Box::new(response);
+    match response.status.unwrap_or(0) {
+        400 => Error::E400BadRequest(response),
+        401 => Error::E401Unauthorized(response),
+        402 => Error::E402PaymentRequired(response),
+        403 => Error::E403Forbidden(response),
+        404 => Error::E404NotFound(response),
+        405 => Error::E405MethodNotAllowed(response),
+        406 => Error::E406NotAcceptable(response),
+        407 => Error::E407ProxyAuthRequired(response),
+        408 => Error::E408RequestTimeout(response),
+        409 => Error::E409Conflict(response),
+        410 => Error::E410Gone(response),
+        411 => Error::E411LengthRequired(response),
+        412 => Error::E412PreconditionFailed(response),
+        413 => Error::E413PayloadTooLarge(response),
+        414 => Error::E414URITooLong(response),
+        415 => Error::E415UnsupportedMediaType(response),
+        416 => Error::E416RangeNotSatisfiable(response),
+        417 => Error::E417ExpectationFailed(response),
+        418 => Error::E418ImATeapot(response),
+        421 => Error::E421MisdirectedRequest(response),
+        422 => Error::E422UnprocessableEntity(response),
+        423 => Error::E423Locked(response),
+        424 => Error::E424FailedDependency(response),
+        425 => Error::E425TooEarly(response),
+        426 => Error::E426UpgradeRequired(response),
+        428 => Error::E428PreconditionRequired(response),
+        429 => Error::E429TooManyRequests(response),
+        431 => Error::E431RequestHeaderFieldsTooLarge(response),
+        451 => Error::E451UnavailableForLegalReasons(response),
+        500 => Error::E500InternalServerError(response),
+        501 => Error::E501NotImplemented(response),
+        502 => Error::E502BadGateway(response),
+        503 => Error::E503ServiceUnavailable(response),
+        504 => Error::E504GatewayTimeout(response),
+        505 => Error::E505HTTPVersionNotSupported(response),
+        506 => Error::E506VariantAlsoNegotiates(response),
+        507 => Error::E507InsufficientStorage(response),
+        508 => Error::E508LoopDetected(response),
+        510 => Error::E510NotExtended(response),
+        511 => Error::E511NetworkAuthenticationRequired(response),
+        code if code >= 500 => Error::E5XXServerError(response),
+        _ => Error::E4XXClientError(response),
+    }
 }
\ No newline at end of file
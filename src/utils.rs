@@ -1,32 +1,52 @@
-use std::collections::HashMap;
-use crate::structs::{Cookie, Response};
+use crate::codec::OutgoingBody;
+use crate::headers::HeaderMap;
+use crate::structs::{Request, Response};
+use crate::types::Error;
 
 pub(crate) mod parsers;
+pub(crate) mod charset;
+pub(crate) mod form;
+pub(crate) mod dates;
 
 use parsers::*;
 
-pub fn new_response(body_text: String, mut head: Vec<String>) -> Response {
-    head.reverse();
-    let head_line = head.pop().unwrap();
-    head.reverse();
-    let mut head_content: Vec<&str> = head_line.split_ascii_whitespace().collect();
-    head_content.reverse();
-    let protocol = head_content.pop().unwrap().to_owned();
-    let status = head_content.pop().unwrap().to_owned();
-    head_content.reverse();
-    let status_text = head_content.join(" ");
-
-
-    let mut cookies = HashMap::<String, Cookie>::new();
-    let mut headers = HashMap::<String, String>::new();
-
-    for line in head {
-        if line.starts_with("Set-Cookie:") {
-            let cookie = parse_cookie(line);
-            cookies.insert(cookie.name.clone(), cookie);
-        } else {
-            let header = parse_header(line);
-            headers.insert(header.name, header.value);
+/// Builds a `Response` from the lines of a response head, the first of which is the status line.
+/// Status lines and header lines which do not follow RFC 7230 are refused with `Error::MalformedResponse`.
+pub fn new_response(body_bytes: Vec<u8>, head: Vec<String>) -> Result<Response, Error> {
+    let mut lines = head.into_iter();
+    let head_line = lines.next().ok_or_else(|| Error::malformed("the response has no status line"))?;
+
+    let mut head_content = head_line.splitn(3, ' ');
+    let protocol = head_content.next().unwrap_or_default().to_owned();
+    let status = head_content.next().unwrap_or_default();
+    let status_text = head_content.next().unwrap_or_default().trim().to_owned();
+
+    if !protocol.starts_with("HTTP/") {
+        return Err(Error::malformed(format!("the status line {:?} does not start with an HTTP version", head_line)));
+    }
+    if status.len() != 3 {
+        return Err(Error::malformed(format!("the status line {:?} does not have a three digit status code", head_line)));
+    }
+    let status = status.parse::<isize>().map_err(|e| Error::malformed_by(format!("the status line {:?} does not have a three digit status code", head_line), e))?;
+
+    let mut cookies = Vec::new();
+    let mut headers = HeaderMap::new();
+    let mut warnings = Vec::new();
+
+    for line in lines {
+        if !line.split_once(':').is_some_and(|(name, _)| is_token(name)) {
+            return Err(Error::malformed(format!("the header line {:?} is not a valid header", line)));
+        }
+
+        let header = parse_header(line);
+        headers.append(header.name, header.value).map_err(|e| Error::malformed_by("the response has a header which is not valid", e))?;
+    }
+
+    // a cookie which cannot be parsed is left out, as browsers ignore it, and reported in the warnings of the response
+    for line in headers.get_all("Set-Cookie") {
+        match parse_cookie(line.to_string()) {
+            Ok(cookie) => cookies.push(cookie),
+            Err(error) => warnings.push(format!("A cookie was ignored: {}", error)),
         }
     }
 
@@ -35,28 +55,116 @@ pub fn new_response(body_text: String, mut head: Vec<String>) -> Response {
 
     let mut body = None;
 
-    if body_text.len() > 0 {
-        body = Some(body_text)
+    if !body_bytes.is_empty() {
+        body = Some(body_bytes)
     }
 
-    Response {
-        raw: body.clone().unwrap_or(String::new()).escape_default().to_string(),
+    Ok(Response {
         protocol: Some(protocol),
-        status: Some(status.parse::<isize>().unwrap()),
+        status: Some(status),
         status_text: Some(status_text),
         cookies,
         cookie_count,
         headers,
         header_count,
         body,
-        warnings: Vec::new(),
+        trailers: HeaderMap::new(),
+        content_encoding: None,
+        compressed_size: None,
+        warnings,
+        redirects: Vec::new(),
+    })
+}
+
+/// Whether `error` shows that the server closed a pooled connection before this request could use it
+pub fn is_stale_connection(error: &Error) -> bool {
+    match error {
+        Error::Io(e) => matches!(e.kind(), std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::BrokenPipe | std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionAborted),
+        _ => false,
+    }
+}
+
+/// Checks the response to an OPTIONS preflight request, and refuses `method` if the server does not allow it.
+/// Servers which do not advertise any CORS or `Allow` headers allow every method.
+pub fn check_preflight(response: &Response, method: &str) -> Result<(), Error> {
+    if let Some(origin) = response.headers.get("Access-Control-Allow-Origin") {
+        if origin.trim() != "*" {
+            return Err(Error::CrossOriginResourceOriginDisallowed);
+        }
     }
+
+    let methods = response.headers.get_combined("Access-Control-Allow-Methods")
+        .or_else(|| response.headers.get_combined("Allow"));
+
+    if let Some(methods) = methods {
+        if !methods.split(',').any(|allowed| allowed.trim() == method || allowed.trim() == "*") {
+            return Err(Error::CrossOriginResourceMethodDisallowed);
+        }
+    }
+
+    Ok(())
 }
 
-pub fn parse_err_reason(reason: String) -> crate::types::Error {
-    if reason == "Method not allowed".to_string() {
-        crate::types::Error::CrossOriginResourceMethodDisallowed
+/// Builds the framing, `Content-Type` and `Content-Encoding` headers for the body of a request.
+/// Methods which expect a body still announce a zero length when none is set, so servers do not wait for content that never arrives.
+pub fn body_headers(request: &Request, body: &OutgoingBody) -> Vec<(&'static str, String)> {
+    if !body.is_present() {
+        return if request.request_type.expects_body() { vec![("Content-Length", String::from("0"))] } else { Vec::new() };
+    }
+
+    let mut headers = match body.length() {
+        Some(length) => vec![("Content-Length", length.to_string())],
+        None => vec![("Transfer-Encoding", String::from("chunked"))],
+    };
+    if let Some(content_type) = body.content_type() {
+        headers.push(("Content-Type", content_type.to_string()));
+    }
+    if let Some(encoding) = body.content_encoding() {
+        headers.push(("Content-Encoding", encoding.to_string()));
+    }
+    headers
+}
+
+/// The headers Curio sends with every request unless the user overrides them
+pub fn default_headers(host: String) -> Vec<(&'static str, String)> {
+    vec![
+        ("User-Agent", String::from("Curio/0.1.0")),
+        ("Host", host),
+        ("Connection", String::from("Keep-Alive")),
+    ]
+}
+
+/// Merges the default headers with the headers set by the user and formats them as the header block of a request, including the empty line which ends it.
+/// User headers replace defaults with the same name (compared case-insensitively), except for the framing headers `Content-Length` and `Transfer-Encoding`, which always describe the body Curio actually sends.
+pub fn build_headers(defaults: Vec<(&str, String)>, user: &HeaderMap) -> String {
+    fn is_framing(name: &str) -> bool {
+        name.eq_ignore_ascii_case("Content-Length") || name.eq_ignore_ascii_case("Transfer-Encoding")
+    }
+
+    let mut block = String::new();
+
+    for (name, value) in defaults.iter() {
+        if is_framing(name) || !user.contains(name) {
+            block.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+
+    for (name, value) in user.iter().filter(|(name, _)| !is_framing(name)) {
+        block.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    block.push_str("\r\n");
+    block
+}
+
+/// Formats the value of the `Host` header, the port is only included when it is not the default port of the protocol.
+/// IPv6 addresses are wrapped in brackets, as they are in urls.
+pub fn host_header(domain: &str, port: usize, default_port: usize) -> String {
+    let host = if domain.contains(':') { format!("[{}]", domain) } else { domain.to_string() };
+
+    if port == default_port {
+        host
     } else {
-        crate::types::Error::CrossOriginResourceOriginDisallowed
+        format!("{}:{}", host, port)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,96 @@
+use std::convert::TryFrom;
+
+use crate::types::Error;
+
+// windows-1252 assigns printable characters to most of the C1 control range which ISO-8859-1 leaves unused
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{FFFD}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{FFFD}', '\u{017D}', '\u{FFFD}',
+    '\u{FFFD}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{FFFD}', '\u{017E}', '\u{0178}',
+];
+
+/// Finds the `charset` parameter of a `Content-Type` header value, without quotes and in lowercase
+pub fn charset_of(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        if name.trim().eq_ignore_ascii_case("charset") {
+            Some(value.trim().trim_matches('"').to_ascii_lowercase())
+        } else {
+            None
+        }
+    })
+}
+
+/// Decodes `bytes` from `charset` into a string, replacing invalid sequences with U+FFFD.
+/// UTF-8, US-ASCII, ISO-8859-1, windows-1252 and UTF-16 are supported, under their common labels.
+pub fn decode(bytes: &[u8], charset: &str) -> Result<String, Error> {
+    match charset.trim().to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" | "unicode-1-1-utf-8" => Ok(String::from_utf8_lossy(strip_bom(bytes, &[0xEF, 0xBB, 0xBF])).into_owned()),
+        "us-ascii" | "ascii" => Ok(bytes.iter().map(|&b| if b.is_ascii() { b as char } else { '\u{FFFD}' }).collect()),
+        "iso-8859-1" | "iso8859-1" | "latin1" | "l1" => Ok(bytes.iter().map(|&b| b as char).collect()),
+        "windows-1252" | "cp1252" | "x-cp1252" => Ok(bytes.iter().map(|&b| match b {
+            0x80..=0x9F => WINDOWS_1252[(b - 0x80) as usize],
+            _ => b as char,
+        }).collect()),
+        "utf-16le" => Ok(decode_utf16(strip_bom(bytes, &[0xFF, 0xFE]), u16::from_le_bytes)),
+        "utf-16be" => Ok(decode_utf16(strip_bom(bytes, &[0xFE, 0xFF]), u16::from_be_bytes)),
+        "utf-16" => match bytes {
+            [0xFF, 0xFE, rest @ ..] => Ok(decode_utf16(rest, u16::from_le_bytes)),
+            [0xFE, 0xFF, rest @ ..] => Ok(decode_utf16(rest, u16::from_be_bytes)),
+            // without a byte order mark UTF-16 is big endian (RFC 2781 section 4.3)
+            _ => Ok(decode_utf16(bytes, u16::from_be_bytes)),
+        },
+        other => Err(Error::UnsupportedCharset(other.to_string())),
+    }
+}
+
+/// Encodes `text` into `charset`, the reverse of `decode`.
+/// Characters which `charset` can not represent are replaced with `?`, as UTF-16 and UTF-8 can represent every character this only affects the single byte character sets.
+pub fn encode(text: &str, charset: &str) -> Result<Vec<u8>, Error> {
+    encode_with(text, charset, |_| String::from("?"))
+}
+
+/// Encodes `text` into `charset` like `encode`, replacing each character which `charset` can not represent with the ASCII text `unmappable` gives for it
+pub fn encode_with<F: Fn(char) -> String>(text: &str, charset: &str, unmappable: F) -> Result<Vec<u8>, Error> {
+    let byte: fn(char) -> Option<u8> = match charset.trim().to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" | "unicode-1-1-utf-8" => return Ok(text.as_bytes().to_vec()),
+        "utf-16le" => return Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+        "utf-16be" => return Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+        // a byte order mark tells the reader which way round the code units are (RFC 2781 section 3.2)
+        "utf-16" => return Ok([0xFE, 0xFF].iter().copied().chain(text.encode_utf16().flat_map(u16::to_be_bytes)).collect()),
+        "us-ascii" | "ascii" => |c| if c.is_ascii() { Some(c as u8) } else { None },
+        "iso-8859-1" | "iso8859-1" | "latin1" | "l1" => |c| u8::try_from(u32::from(c)).ok(),
+        "windows-1252" | "cp1252" | "x-cp1252" => |c| match WINDOWS_1252.iter().position(|mapped| *mapped == c && c != '\u{FFFD}') {
+            Some(index) => Some(0x80 + index as u8),
+            None => match u32::from(c) {
+                code @ (0x00..=0x7F | 0xA0..=0xFF) => Some(code as u8),
+                _ => None,
+            },
+        },
+        other => return Err(Error::UnsupportedCharset(other.to_string())),
+    };
+
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match byte(c) {
+            Some(b) => bytes.push(b),
+            None => bytes.extend_from_slice(unmappable(c).as_bytes()),
+        }
+    }
+    Ok(bytes)
+}
+
+fn strip_bom<'a>(bytes: &'a [u8], bom: &[u8]) -> &'a [u8] {
+    bytes.strip_prefix(bom).unwrap_or(bytes)
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks(2).map(|pair| match pair {
+        [first, second] => unit([*first, *second]),
+        // a trailing odd byte can not be part of a valid code unit
+        _ => 0xFFFD,
+    });
+
+    std::char::decode_utf16(units).map(|c| c.unwrap_or('\u{FFFD}')).collect()
+}
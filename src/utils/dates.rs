@@ -0,0 +1,172 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Parses the date of a cookie's `Expires` attribute with the lenient algorithm of RFC 6265 section 5.1.1,
+/// which accepts the IMF-fixdate, RFC 850 and asctime formats as well as most of the variations servers send.
+/// Returns `None` for a date which is missing a part or names a day which does not exist.
+pub fn parse_cookie_date(date: &str) -> Option<SystemTime> {
+    let (mut time, mut day, mut month, mut year) = (None, None, None, None);
+
+    for token in date.split(is_delimiter).filter(|token| !token.is_empty()) {
+        if time.is_none() {
+            if let Some(parsed) = parse_time(token) {
+                time = Some(parsed);
+                continue;
+            }
+        }
+        if day.is_none() {
+            if let Some(parsed) = leading_number(token, 1, 2) {
+                day = Some(parsed);
+                continue;
+            }
+        }
+        if month.is_none() {
+            if let Some(index) = token.get(..3).and_then(|prefix| MONTHS.iter().position(|month| prefix.eq_ignore_ascii_case(month))) {
+                month = Some(index as u32 + 1);
+                continue;
+            }
+        }
+        if year.is_none() {
+            if let Some(parsed) = leading_number(token, 2, 4) {
+                year = Some(parsed);
+            }
+        }
+    }
+
+    let year = match year? {
+        year @ 70..=99 => year + 1900,
+        year @ 0..=69 => year + 2000,
+        year => year,
+    };
+    let (hour, minute, second) = time?;
+    let (day, month) = (day?, month?);
+
+    if !(1..=31).contains(&day) || year < 1601 || hour > 23 || minute > 59 || second > 59 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(timestamp(year, month, day, hour, minute, second))
+}
+
+/// Parses an `HTTP-date` in the IMF-fixdate format, or in the obsolete RFC 850 and asctime formats which recipients must still accept (RFC 7231 section 7.1.1.1).
+/// Unlike `parse_cookie_date` every part must be where the format puts it, so `None` is returned for anything else.
+pub fn parse_http_date(date: &str) -> Option<SystemTime> {
+    let fields = date.split(' ').filter(|field| !field.is_empty()).collect::<Vec<&str>>();
+
+    let (day, month, year, time) = match fields.as_slice() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        [weekday, day, month, year, time, "GMT"] if is_weekday(weekday.strip_suffix(',')?, false) && day.len() == 2 && year.len() == 4 => (*day, *month, exact_number(year, 4, 4)?, *time),
+        // Sunday, 06-Nov-94 08:49:37 GMT
+        [weekday, date, time, "GMT"] if is_weekday(weekday.strip_suffix(',')?, true) => {
+            let mut parts = date.splitn(3, '-');
+            let (day, month, year) = (parts.next()?, parts.next()?, exact_number(parts.next()?, 2, 2)?);
+            (day, month, if year < 70 { year + 2000 } else { year + 1900 }, *time)
+        }
+        // Sun Nov  6 08:49:37 1994
+        [weekday, month, day, time, year] if is_weekday(weekday, false) => (*day, *month, exact_number(year, 4, 4)?, *time),
+        _ => return None,
+    };
+
+    let day = exact_number(day, 1, 2)?;
+    let month = MONTHS.iter().position(|name| month.len() == 3 && month.eq_ignore_ascii_case(name))? as u32 + 1;
+    let mut clock = time.split(':');
+    let (hour, minute, second) = (exact_number(clock.next()?, 2, 2)?, exact_number(clock.next()?, 2, 2)?, exact_number(clock.next()?, 2, 2)?);
+
+    // a leap second is allowed by the grammar, and is read as the last second of the minute
+    if clock.next().is_some() || day == 0 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some(timestamp(year, month, day, hour, minute, second.min(59)))
+}
+
+/// Formats `time` as an IMF-fixdate, such as `Sun, 06 Nov 1994 08:49:37 GMT`, which is the format senders must use for an `HTTP-date`.
+/// Times before the Unix epoch are formatted as the epoch, and fractions of a second are dropped
+pub fn format_http_date(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+    let (days, seconds) = ((seconds / 86400) as i64, seconds % 86400);
+    let (year, month, day) = civil_from_days(days);
+
+    // 1970-01-01 was a Thursday
+    let weekday = WEEKDAYS[(days + 3).rem_euclid(7) as usize];
+    let month = MONTHS[month as usize - 1];
+    format!("{}, {:02} {}{} {} {:02}:{:02}:{:02} GMT", weekday, day, month[..1].to_ascii_uppercase(), &month[1..], year,
+            seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// The time at `hour:minute:second` UTC on the given day of the proleptic Gregorian calendar
+pub fn timestamp(year: u32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> SystemTime {
+    let seconds = days_from_civil(year, month, day) * 86400 + i64::from(hour * 3600 + minute * 60 + second);
+
+    if seconds >= 0 {
+        UNIX_EPOCH + Duration::from_secs(seconds as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs())
+    }
+}
+
+// delimiter = %x09 / %x20-2F / %x3B-40 / %x5B-60 / %x7B-7E
+fn is_delimiter(c: char) -> bool {
+    matches!(c, '\t' | ' '..='/' | ';'..='@' | '['..='`' | '{'..='~')
+}
+
+/// Reads `hms-time = time-field ":" time-field ":" time-field`, where each field is one or two digits
+fn parse_time(token: &str) -> Option<(u32, u32, u32)> {
+    let mut fields = token.splitn(3, ':');
+    let hour = exact_number(fields.next()?, 1, 2)?;
+    let minute = exact_number(fields.next()?, 1, 2)?;
+    let second = leading_number(fields.next()?, 1, 2)?;
+    Some((hour, minute, second))
+}
+
+/// Reads between `min` and `max` digits at the start of `token`, which may be followed by anything other than another digit
+fn leading_number(token: &str, min: usize, max: usize) -> Option<u32> {
+    let digits = token.bytes().take_while(u8::is_ascii_digit).count();
+    if digits < min || digits > max {
+        return None;
+    }
+    token[..digits].parse().ok()
+}
+
+fn exact_number(token: &str, min: usize, max: usize) -> Option<u32> {
+    leading_number(token, min, max).filter(|_| token.bytes().all(|b| b.is_ascii_digit()))
+}
+
+// day-name is the short name in IMF-fixdate and asctime, and the full name in RFC 850 dates
+fn is_weekday(name: &str, long: bool) -> bool {
+    const LONG: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+    if long { LONG.contains(&name) } else { WEEKDAYS.contains(&name) }
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The number of days between 1970-01-01 and the given date (Howard Hinnant's `days_from_civil`)
+fn days_from_civil(year: u32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The date which is `days` after 1970-01-01, the reverse of `days_from_civil` (Howard Hinnant's `civil_from_days`)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
@@ -0,0 +1,82 @@
+/// Serializes `pairs` as an `application/x-www-form-urlencoded` string, following the WHATWG URL standard (section 5.2).
+/// Names and values are encoded in `output_charset(charset)`, or UTF-8 if it is not supported, with characters the charset can not represent written as `&#NNNN;` numeric character references.
+/// Then spaces become `+` and every byte other than ASCII alphanumerics and `*-._` is percent-encoded.
+pub fn encode<'a, I: IntoIterator<Item = &'a (String, String)>>(pairs: I, charset: &str) -> String {
+    let mut form = String::new();
+
+    for (name, value) in pairs {
+        if !form.is_empty() {
+            form.push('&');
+        }
+        encode_into(&mut form, name, charset);
+        form.push('=');
+        encode_into(&mut form, value, charset);
+    }
+    form
+}
+
+/// Parses an `application/x-www-form-urlencoded` body into its name-value pairs, in order and keeping repeated names (WHATWG URL standard section 5.1).
+/// Parsing never fails: empty sequences are skipped, a sequence without `=` has an empty value, and bytes which are not valid UTF-8 become U+FFFD.
+pub fn decode(input: &[u8]) -> Vec<(String, String)> {
+    input.split(|b| *b == b'&')
+        .filter(|sequence| !sequence.is_empty())
+        .map(|sequence| {
+            let (name, value) = match sequence.iter().position(|b| *b == b'=') {
+                Some(split) => (&sequence[..split], &sequence[split + 1..]),
+                None => (sequence, &[][..]),
+            };
+            (decode_component(name), decode_component(value))
+        })
+        .collect()
+}
+
+/// The charset a form is actually encoded in when `charset` is asked for: UTF-16 is never used for forms, they fall back to UTF-8 (WHATWG Encoding standard section 4.3)
+pub fn output_charset(charset: &str) -> &str {
+    match charset.trim().to_ascii_lowercase().as_str() {
+        "utf-16" | "utf-16le" | "utf-16be" => "utf-8",
+        _ => charset,
+    }
+}
+
+fn encode_into(form: &mut String, component: &str, charset: &str) {
+    let bytes = super::charset::encode_with(component, output_charset(charset), |c| format!("&#{};", u32::from(c))).unwrap_or_else(|_| component.as_bytes().to_vec());
+
+    for byte in bytes {
+        match byte {
+            b'*' | b'-' | b'.' | b'_' | b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z' => form.push(byte as char),
+            b' ' => form.push('+'),
+            _ => form.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+}
+
+fn decode_component(component: &[u8]) -> String {
+    let mut bytes = Vec::with_capacity(component.len());
+    let mut index = 0;
+
+    while index < component.len() {
+        match component[index] {
+            b'+' => bytes.push(b' '),
+            // a `%` which is not followed by two hexadecimal digits is kept as it is
+            b'%' => match component.get(index + 1..index + 3).and_then(hex_byte) {
+                Some(byte) => {
+                    bytes.push(byte);
+                    index += 2;
+                }
+                None => bytes.push(b'%'),
+            },
+            byte => bytes.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn hex_byte(digits: &[u8]) -> Option<u8> {
+    let digits = std::str::from_utf8(digits).ok()?;
+    if digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        u8::from_str_radix(digits, 16).ok()
+    } else {
+        None
+    }
+}
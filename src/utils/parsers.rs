@@ -1,140 +1,252 @@
-use std::collections::HashMap;
-
+use crate::cookies::SameSite;
 use crate::structs::{Cookie, Header, HTTPProtocol};
+use crate::types::Error;
+use crate::utils::dates::parse_cookie_date;
+
+// the limits browsers apply, from the successor draft of RFC 6265 (section 5.6)
+const MAX_NAME_VALUE_LENGTH: usize = 4096;
+const MAX_ATTRIBUTE_VALUE_LENGTH: usize = 1024;
+
+/// Parses a `Set-Cookie` header following RFC 6265 section 5.2, given either its value or the whole header line.
+/// Attribute names are matched ignoring case, and attributes which are unknown or have invalid values are ignored as browsers ignore them.
+/// A cookie is refused with `Error::InvalidCookie` when it has neither a name nor a value, contains control characters, or is longer than 4096 bytes.
+pub fn parse_cookie(line: String) -> Result<Cookie, Error> {
+    let header = match line.split_once(':') {
+        Some((name, value)) if name.trim().eq_ignore_ascii_case("Set-Cookie") => value,
+        _ => line.as_str(),
+    };
+    let invalid = |reason: &str| Error::InvalidCookie { cookie: header.trim().to_string(), reason: reason.to_string() };
 
-const KEY: &str = "N4M3";
-const VALUE: &str = "V41U3";
-const HTTP_ONLY: &str = "HttpOnly";
-const SECURE: &str = "Secure";
+    let (pair, attributes) = header.split_once(';').unwrap_or((header, ""));
+    // a pair without `=` is a cookie with an empty name, as browsers treat it
+    let (name, value) = pair.split_once('=').unwrap_or(("", pair));
+    let (name, value) = (trim_whitespace(name), trim_whitespace(value));
 
-pub fn parse_cookie(line: String) -> Cookie {
-    fn unwrap_parsed_arg<T, F: FnOnce(&String) -> T>(parsed_arg: Option<&String>, unwrap: F) -> Option<T> {
-        match parsed_arg {
-            Some(x) => Some(unwrap(x)),
-            None => None,
+    if name.is_empty() && value.is_empty() {
+        return Err(invalid("the cookie has neither a name nor a value"));
+    }
+    if name.len() + value.len() > MAX_NAME_VALUE_LENGTH {
+        return Err(invalid("the name and value of the cookie are longer than 4096 bytes"));
+    }
+    if name.chars().chain(value.chars()).any(|c| c.is_control() && c != '\t') {
+        return Err(invalid("the cookie contains a control character"));
+    }
+
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        value: value.to_string(),
+        expires: None,
+        max_age: None,
+        path: None,
+        domain: None,
+        same_site: None,
+        http_only: false,
+        secure: false,
+    };
+
+    // later attributes replace earlier ones with the same name
+    for attribute in attributes.split(';') {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let (key, value) = (trim_whitespace(key), trim_whitespace(value));
+        if value.len() > MAX_ATTRIBUTE_VALUE_LENGTH {
+            continue;
+        }
+
+        match key.to_ascii_lowercase().as_str() {
+            "expires" => {
+                if let Some(expires) = parse_cookie_date(value) {
+                    cookie.expires = Some(expires);
+                }
+            }
+            "max-age" => {
+                if let Some(max_age) = parse_max_age(value) {
+                    cookie.max_age = Some(max_age);
+                }
+            }
+            "domain" if !value.is_empty() => cookie.domain = Some(value.trim_start_matches('.').to_ascii_lowercase()),
+            "path" => cookie.path = Some(value.to_string()).filter(|path| path.starts_with('/')),
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            "samesite" => cookie.same_site = SameSite::parse(value),
+            _ => {}
         }
     }
 
-    let formatted = line.split("Set-Cookie:").collect::<Vec<&str>>();
-    let args = formatted.last().unwrap().split(';').collect::<Vec<&str>>();
-    let mut parsed_args = HashMap::<String, String>::new();
+    Ok(cookie)
+}
 
-    let mut http_only = false;
-    let mut secure = false;
+/// Reads `max-age-av`, an optional `-` followed by digits. Values too large for an `i64` are clamped, as they are in the distant future either way
+fn parse_max_age(value: &str) -> Option<i64> {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    match value.parse::<i64>() {
+        Ok(max_age) => Some(max_age),
+        Err(_) if value.starts_with('-') => Some(i64::MIN),
+        Err(_) => Some(i64::MAX),
+    }
+}
 
-    for index in 0..args.len() {
-        let arg = args.get(index).unwrap();
+// cookies only treat spaces and tabs as whitespace
+fn trim_whitespace(value: &str) -> &str {
+    value.trim_matches(|c| c == ' ' || c == '\t')
+}
 
-        let mut keypair = arg.split("=").collect::<Vec<&str>>();
-        keypair.reverse();
-        let mut key_name = keypair.pop().unwrap().split(" ").collect::<Vec<&str>>();
+pub fn parse_header(line: String) -> Header {
+    let (key, value) = line.split_once(':').unwrap_or((line.as_str(), ""));
+    Header {
+        name: key.to_string(),
+        value: value.trim().to_string(),
+    }
+}
 
-        let key = match key_name.len() {
-            1 => key_name.last().unwrap().to_string(),
-            0 => String::new(),
-            _ => {
-                key_name.reverse();
-                key_name.pop();
-                key_name.reverse();
+/// Splits a url into its protocol, host, port and path (including any query string).
+/// Urls which cannot be parsed give an empty host, `Request::send` reports these with the reason from `try_parse_url`.
+pub fn parse_url(url: &str) -> (HTTPProtocol, String, usize, String) {
+    try_parse_url(url).unwrap_or((HTTPProtocol::HTTP, String::new(), 0, String::from("/")))
+}
 
-                key_name.join(" ")
-            }
-        };
+/// Splits an absolute `http` or `https` url into its protocol, host, port and path, or explains why it cannot be sent to
+pub fn try_parse_url(url: &str) -> Result<(HTTPProtocol, String, usize, String), Error> {
+    let invalid = |reason: &str| Error::InvalidUrl { url: url.to_string(), reason: reason.to_string() };
 
-        let value = keypair.join("=");
+    let (scheme, rest) = url.trim().split_once("://").ok_or_else(|| invalid("the url does not start with http:// or https://"))?;
+    let (protocol, default_port) = match scheme.to_ascii_lowercase().as_str() {
+        "http" => (HTTPProtocol::HTTP, 80),
+        "https" => (HTTPProtocol::HTTPS, 443),
+        _ => return Err(invalid("only http and https urls are supported")),
+    };
 
-        if index == 0 {
-            parsed_args.insert(KEY.to_owned(), key);
-            parsed_args.insert(VALUE.to_owned(), value);
-        } else {
-            match key.as_str() {
-                HTTP_ONLY => { http_only = true; }
-                SECURE => { secure = true; }
-                _ => { parsed_args.insert(key, value); }
-            };
-        }
-    }
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (authority, path) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+    let authority = authority.rsplit('@').next().unwrap_or_default();
+
+    let (domain, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((domain, "")) => (domain, None),
+            Some((domain, port)) => (domain, Some(port.strip_prefix(':').ok_or_else(|| invalid("the IPv6 address is followed by something other than a port"))?)),
+            None => return Err(invalid("the IPv6 address is missing its closing bracket")),
+        },
+        None => match authority.split_once(':') {
+            Some((domain, port)) => (domain, Some(port)),
+            None => (authority, None),
+        },
+    };
 
-    let name = parsed_args.get(KEY)
-        .unwrap()
-        .clone();
+    if domain.is_empty() {
+        return Err(invalid("the url has no host"));
+    }
 
-    let value = parsed_args.get(VALUE)
-        .unwrap()
-        .clone();
+    let port = match port {
+        Some(port) if !port.is_empty() => port.parse::<u16>().map_err(|_| invalid("the port is not a number between 0 and 65535"))? as usize,
+        _ => default_port,
+    };
 
-    let clone = |s: &String| s.clone();
+    let path = match path.chars().next() {
+        Some('/') => path.to_string(),
+        _ => format!("/{}", path),
+    };
+    check_path(url, &path)?;
 
-    let expires = unwrap_parsed_arg(parsed_args.get("Expires"), clone);
+    Ok((protocol, domain.to_string(), port, path))
+}
 
-    let max_age = unwrap_parsed_arg(parsed_args.get("Max-Age"), |s| {
-        s.parse::<usize>().expect("Failed to parse 'Max-Age' as {integer}")
-    });
+/// Checks that `path`, the path and query of `url`, can be written into the request line.
+/// Spaces and control characters would end the request line early and let the rest of the path inject headers, so they and any non-ASCII characters must be percent-encoded
+pub fn check_path(url: &str, path: &str) -> Result<(), Error> {
+    if !path.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(Error::InvalidUrl { url: url.to_string(), reason: String::from("the path contains spaces, control characters or non-ASCII characters, which must be percent-encoded") });
+    }
+    Ok(())
+}
 
-    let path = unwrap_parsed_arg(parsed_args.get("Path"), clone);
+/// Resolves `reference`, such as the value of a `Location` header, against the absolute url `base` (RFC 3986 section 5.2).
+/// Absolute references are returned as they are, any fragment is dropped as it is never sent to the server.
+/// Spaces, control characters and non-ASCII characters in `reference` are percent-encoded, as browsers do.
+pub fn resolve_url(base: &str, reference: &str) -> Result<String, Error> {
+    let reference = percent_encode_unsafe(reference.trim());
+    if has_scheme(&reference) {
+        return Ok(reference);
+    }
 
-    let domain = unwrap_parsed_arg(parsed_args.get("Domain"), clone);
+    let (scheme, rest) = base.trim().split_once("://").ok_or_else(|| Error::InvalidUrl { url: base.to_string(), reason: String::from("the url does not start with http:// or https://") })?;
+    if let Some(network_path) = reference.strip_prefix("//") {
+        return Ok(format!("{}://{}", scheme, network_path));
+    }
 
-    let same_site = unwrap_parsed_arg(parsed_args.get("SameSite"), clone);
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (authority, path_and_query) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+    let base_path = match path_and_query.split('?').next().unwrap_or_default() {
+        "" => "/",
+        path => path,
+    };
 
-    return Cookie {
-        name,
-        value,
-        expires,
-        max_age,
-        path,
-        http_only,
-        domain,
-        same_site,
-        secure,
+    let reference = reference.split('#').next().unwrap_or_default();
+    let target = if reference.is_empty() {
+        if path_and_query.starts_with('/') { path_and_query.to_string() } else { format!("/{}", path_and_query) }
+    } else if reference.starts_with('?') {
+        format!("{}{}", base_path, reference)
+    } else if reference.starts_with('/') {
+        remove_dot_segments(reference)
+    } else {
+        let directory = &base_path[..base_path.rfind('/').map_or(0, |index| index + 1)];
+        remove_dot_segments(&format!("{}{}", directory, reference))
     };
+
+    Ok(format!("{}://{}{}", scheme, authority, target))
 }
 
-pub fn parse_header(line: String) -> Header {
-    let mut keypair = line.split(": ").collect::<Vec<&str>>();
-    keypair.reverse();
-    let key_name = keypair.pop().unwrap().split(" ").collect::<Vec<&str>>();
-    let mut key: String = key_name.join(" ");
-    if key_name.len() == 1 as usize {
-        key = key_name.last().unwrap().to_string();
+/// Percent-encodes every byte which can not appear in a url as it is, leaving existing escapes alone
+fn percent_encode_unsafe(reference: &str) -> String {
+    let mut encoded = String::with_capacity(reference.len());
+    for b in reference.bytes() {
+        if b.is_ascii_graphic() {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
     }
-    let mut value = keypair.join("=");
-    value = value.split("\r\n").collect::<Vec<&str>>().join("");
-    return Header {
-        name: key,
-        value,
-    };
+    encoded
 }
 
-pub fn parse_url(url: &String) -> (HTTPProtocol, String, usize, String) {
-    let mut http = HTTPProtocol::HTTP;
-    if url.contains("https") {
-        http = HTTPProtocol::HTTPS;
+/// Whether `reference` starts with a scheme such as `https:`, which makes it an absolute url
+fn has_scheme(reference: &str) -> bool {
+    match reference.split_once(':') {
+        Some((scheme, _)) => scheme.starts_with(|c: char| c.is_ascii_alphabetic()) && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)),
+        None => false,
     }
-    let protless_url_vec = url.split(r"/^(?:https?:\/\/)/igm").collect::<Vec<&str>>();
-    let protless_url = protless_url_vec.last().unwrap();
-    let mut url_parts = protless_url.split("/").collect::<Vec<&str>>();
-    url_parts.reverse();
-    url_parts.pop();
-    url_parts.pop();
-    let domain_str = url_parts.pop().unwrap();
-
-    let mut port: usize = match http {
-        HTTPProtocol::HTTP => 80,
-        HTTPProtocol::HTTPS => 443,
-    };
+}
 
-    let mut domain = String::new();
+/// Removes the `.` and `..` segments from an absolute path, keeping any query string as it is
+fn remove_dot_segments(path: &str) -> String {
+    let (path, query) = match path.find('?') {
+        Some(index) => path.split_at(index),
+        None => (path, ""),
+    };
 
-    if domain_str.contains(":") {
-        let mut domain_components = domain_str.split(":").collect::<Vec<&str>>();
-        port = domain_components.pop().unwrap().parse().unwrap();
-        domain = domain_components.join("");
-    } else {
-        domain = domain_str.to_string()
+    let mut segments: Vec<&str> = Vec::new();
+    let mut ends_in_directory = false;
+    for segment in path.split('/').skip(1) {
+        ends_in_directory = segment == "." || segment == "..";
+        match segment {
+            "." => {}
+            ".." => { segments.pop(); }
+            segment => segments.push(segment),
+        }
+    }
+    if ends_in_directory {
+        segments.push("");
     }
 
-    url_parts.reverse();
-    let path = format!("/{}", url_parts.join("/"));
-    return (http, domain, port, path);
+    format!("/{}{}", segments.join("/"), query)
+}
+
+/// Checks whether `value` is a valid HTTP token (RFC 7230 section 3.2.6), as used for method and header names
+pub fn is_token(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(is_tchar)
+}
+
+pub fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
 }